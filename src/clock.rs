@@ -0,0 +1,69 @@
+use std::sync::{Arc, Mutex};
+
+/// Network-wide simulation clock with a fixed time step `dt`. Clones share
+/// one step counter, so every component holding a clone reads the same
+/// time. Whatever drives the network calls `tick` once per step, after all
+/// components have fired.
+#[derive(Clone)]
+pub struct Clock {
+    dt: f64,
+    steps: Arc<Mutex<u64>>,
+}
+
+impl Clock {
+    pub fn new(dt: f64) -> Self {
+        if !(dt > 0.0) {
+            panic!("Time step must be positive (actual: {})", dt);
+        }
+        Clock {
+            dt: dt,
+            steps: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    pub fn get_dt(&self) -> f64 {
+        self.dt
+    }
+
+    pub fn get_steps(&self) -> u64 {
+        *self.steps.lock().unwrap()
+    }
+
+    /// Time at the current step, computed from the step count so that it
+    /// does not accumulate rounding error.
+    pub fn get_time(&self) -> f64 {
+        self.get_steps() as f64 * self.dt
+    }
+
+    pub fn tick(&self) {
+        *self.steps.lock().unwrap() += 1;
+    }
+
+    pub fn reset(&self) {
+        *self.steps.lock().unwrap() = 0;
+    }
+
+    pub fn is_shared_with(&self, other: &Clock) -> bool {
+        Arc::ptr_eq(&self.steps, &other.steps)
+    }
+}
+
+#[test]
+fn clock_works() {
+    let c0 = Clock::new(0.25);
+    let c1 = c0.clone();
+    let c2 = Clock::new(0.25);
+
+    c0.tick();
+    c1.tick();
+
+    assert_eq!(c0.get_steps(), 2);
+    assert_eq!(c1.get_time(), 0.5);
+    assert_eq!(c2.get_time(), 0.0);
+    assert!(c0.is_shared_with(&c1));
+    assert!(!c0.is_shared_with(&c2));
+
+    c1.reset();
+
+    assert_eq!(c0.get_time(), 0.0);
+}
@@ -0,0 +1,140 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Function {
+    Sigmoid,
+    Tanh,
+    Relu,
+    LeakyRelu(f64),
+    Softplus,
+    Softmax(i32),
+    Threshold(f64),
+}
+
+pub fn activate(function: Function, x: &af::Array) -> af::Array {
+    let dims = x.dims();
+    let zeros = af::constant(0.0, dims);
+    match function {
+        Function::Sigmoid => af::sigmoid(x),
+        Function::Tanh => af::tanh(x),
+        Function::Relu => af::maxof(x, &zeros, false),
+        Function::LeakyRelu(alpha) => {
+            let positive = af::maxof(x, &zeros, false);
+            let negative = af::minof(x, &zeros, false);
+            af::add(&positive, &af::mul(&negative, &af::constant(alpha, dims), false), false)
+        },
+        Function::Softplus => {
+            let positive = af::maxof(x, &zeros, false);
+            let tail = af::log1p(&af::exp(&af::mul(&af::abs(x), &af::constant(-1.0, dims), false)));
+            af::add(&positive, &tail, false)
+        },
+        Function::Softmax(dim) => {
+            let shifted = af::sub(x, &af::max(x, dim), true);
+            let exps = af::exp(&shifted);
+            af::div(&exps, &af::sum(&exps, dim), true)
+        },
+        Function::Threshold(theta) => af::gt(x, &af::constant(theta, dims), false).cast::<f64>(),
+    }
+}
+
+/// Applies `function` to "in" and writes the result, of the same shape, to
+/// "out".
+#[derive(Clone)]
+pub struct Activation {
+    base: ComponentStruct,
+    function: Function,
+}
+
+impl Activation {
+    pub fn new(function: Function, dims: af::Dim4) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", dims);
+        Activation {
+            base: base,
+            function: function,
+        }
+    }
+}
+
+impl Unit for Activation {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Activation {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let value = activate(self.function, &*x);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn activation_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let n_rows: u64 = 5;
+    let n_cols: u64 = 3;
+
+    let dims = af::Dim4::new(&[n_rows, n_cols, 1, 1]);
+    let minus = af::constant(-2.0, dims);
+
+    let (r0, _) = af::sum_all(&activate(Function::Relu, &minus));
+    let (r1, _) = af::sum_all(&activate(Function::LeakyRelu(0.5), &minus));
+    let (r2, _) = af::sum_all(&activate(Function::Threshold(-3.0), &minus));
+    let (r3, _) = af::sum_all(&activate(Function::Sigmoid, &af::constant(0.0, dims)));
+
+    assert_eq!(r0, 0.0);
+    assert_eq!(r1, -15.0);
+    assert_eq!(r2, 15.0);
+    assert_eq!(r3, 7.5);
+
+    let x = af::Array::new(&[-1000.0, 0.0, 1000.0], af::Dim4::new(&[3, 1, 1, 1]));
+    let mut r4 = [0.0; 3];
+    activate(Function::Softplus, &x).host(&mut r4);
+
+    assert_eq!(r4[0], 0.0);
+    assert!((r4[1] - 2.0f64.ln()).abs() < 1e-12);
+    assert_eq!(r4[2], 1000.0);
+
+    let mut c0 = Activation::new(Function::Softmax(0), dims);
+
+    c0.get_in_port("in").unwrap().write(Arc::new(minus));
+    c0.input();
+    c0.fire();
+    c0.output();
+
+    let a0 = c0.get_out_port("out").unwrap().read();
+    let (r0, _) = af::sum_all(&a0);
+
+    assert_eq!(a0.dims(), dims);
+    assert!((r0 - 3.0).abs() < 1e-9);
+}
@@ -0,0 +1,267 @@
+use port::Port;
+use unit::*;
+use component::*;
+use component::activation::{Function, activate};
+use random::Stream;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use af;
+
+/// Linear critic over the feature vector on "state" (`n` × 1). Each step it
+/// computes the TD error `δ = r + γ V(s) - V(s_prev)` from "reward" and
+/// writes it to "td_error", so that it can be broadcast as a modulatory
+/// signal, and writes `V(s)` to "value". A nonzero "done" marks `s` as
+/// terminal. The error is zero on the first step of every episode.
+#[derive(Clone)]
+pub struct Critic {
+    base: ComponentStruct,
+    alpha: f64,
+    gamma: f64,
+    weights: af::Array,
+    previous: Option<af::Array>,
+}
+
+impl Critic {
+    pub fn new(n: u64, alpha: f64, gamma: f64) -> Self {
+        let unit = af::Dim4::new(&[1, 1, 1, 1]);
+        let mut base = ComponentStruct::new();
+        base.make_in_port("state", af::Dim4::new(&[n, 1, 1, 1]));
+        base.make_in_port("reward", unit);
+        base.make_in_port("done", unit);
+        base.make_out_port("value", unit);
+        base.make_out_port("td_error", unit);
+        Critic {
+            base: base,
+            alpha: alpha,
+            gamma: gamma,
+            weights: af::constant(0.0, af::Dim4::new(&[1, n, 1, 1])),
+            previous: None,
+        }
+    }
+
+    pub fn get_weights(&self) -> &af::Array {
+        &self.weights
+    }
+
+    fn evaluate(&self, x: &af::Array) -> f64 {
+        scalar(&af::matmul(&self.weights, x, af::MatProp::NONE, af::MatProp::NONE))
+    }
+
+    fn step(&mut self, x: &af::Array, reward: f64, done: bool) -> (f64, f64) {
+        let value = if done { 0.0 } else { self.evaluate(x) };
+        let error = match self.previous.take() {
+            Some(x_prev) => {
+                let error = reward + self.gamma * value - self.evaluate(&x_prev);
+                let dw = &x_prev * &af::constant(self.alpha * error, x_prev.dims());
+                self.weights = &self.weights + &af::transpose(&dw, false);
+                error
+            },
+            None => 0.0,
+        };
+        self.previous = if done { None } else { Some(x.clone()) };
+        (value, error)
+    }
+}
+
+impl Unit for Critic {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Critic {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("state");
+        let reward = scalar(&*self.base.get_input("reward"));
+        let done = scalar(&*self.base.get_input("done")) != 0.0;
+        let (value, error) = self.step(&*x, reward, done);
+        let unit = af::Dim4::new(&[1, 1, 1, 1]);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("value".to_string(), Arc::new(af::constant(value, unit)));
+        outputs.insert("td_error".to_string(), Arc::new(af::constant(error, unit)));
+        self.base.outputs = outputs;
+    }
+}
+
+#[derive(Clone)]
+struct Decision {
+    features: af::Array,
+    policy: af::Array,
+    action: usize,
+}
+
+/// Softmax policy over `n_actions` actions from the feature vector on
+/// "state" (`n` × 1). Each step samples an action index to "action" and
+/// writes the action probabilities to "policy". The TD error read from
+/// "td_error" reinforces the decision made `lag` steps earlier; a critic
+/// firing in the same step as the actor delivers the error for a decision
+/// two steps after it was made, one step for the reward to arrive and one
+/// for the port to carry it.
+#[derive(Clone)]
+pub struct Actor {
+    base: ComponentStruct,
+    beta: f64,
+    lag: usize,
+    preferences: af::Array,
+    history: VecDeque<Decision>,
+    stream: Stream,
+}
+
+impl Actor {
+    pub fn new(n: u64, n_actions: u64, beta: f64, lag: usize, stream: Stream) -> Self {
+        if lag == 0 {
+            panic!("TD error lag must be positive");
+        }
+        let unit = af::Dim4::new(&[1, 1, 1, 1]);
+        let mut base = ComponentStruct::new();
+        base.make_in_port("state", af::Dim4::new(&[n, 1, 1, 1]));
+        base.make_in_port("td_error", unit);
+        base.make_out_port("action", unit);
+        base.make_out_port("policy", af::Dim4::new(&[n_actions, 1, 1, 1]));
+        Actor {
+            base: base,
+            beta: beta,
+            lag: lag,
+            preferences: af::constant(0.0, af::Dim4::new(&[n_actions, n, 1, 1])),
+            history: VecDeque::new(),
+            stream: stream,
+        }
+    }
+
+    pub fn get_preferences(&self) -> &af::Array {
+        &self.preferences
+    }
+
+    fn learn(&mut self, error: f64) {
+        if self.history.len() < self.lag {
+            return;
+        }
+        let decision = match self.history.pop_front() {
+            Some(x) => x,
+            None    => return,
+        };
+        let n_actions = self.preferences.dims()[0] as usize;
+        let mut chosen = vec![0.0; n_actions];
+        chosen[decision.action] = 1.0;
+        let chosen = af::Array::new(&chosen, decision.policy.dims());
+        let advantage = &(&chosen - &decision.policy) * &af::constant(self.beta * error, decision.policy.dims());
+        let dp = af::matmul(&advantage, &decision.features, af::MatProp::NONE, af::MatProp::TRANS);
+        self.preferences = &self.preferences + &dp;
+    }
+
+    fn step(&mut self, x: &af::Array, error: f64) -> (usize, af::Array) {
+        self.learn(error);
+
+        let scores = af::matmul(&self.preferences, x, af::MatProp::NONE, af::MatProp::NONE);
+        let policy = activate(Function::Softmax(0), &scores);
+        let mut probabilities = vec![0.0; policy.elements() as usize];
+        policy.host(&mut probabilities);
+
+        let action = self.stream.categorical(&probabilities);
+
+        self.history.push_back(Decision {
+            features: x.clone(),
+            policy: policy.clone(),
+            action: action,
+        });
+        (action, policy)
+    }
+}
+
+impl Unit for Actor {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Actor {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("state");
+        let error = scalar(&*self.base.get_input("td_error"));
+        let (action, policy) = self.step(&*x, error);
+        let value = af::constant(action as f64, af::Dim4::new(&[1, 1, 1, 1]));
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("action".to_string(), Arc::new(value));
+        outputs.insert("policy".to_string(), Arc::new(policy));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn actor_critic_works() {
+    use random::Random;
+
+    af::set_backend(af::Backend::CPU);
+
+    let unit = af::Dim4::new(&[1, 1, 1, 1]);
+    let features = af::constant(1.0, unit);
+
+    let mut c0 = Critic::new(1, 0.5, 0.0);
+    let mut c1 = Actor::new(1, 2, 1.0, 2, Random::new(0).stream("actor"));
+
+    connect(&mut c1, "td_error", &mut c0, "td_error");
+
+    c0.get_in_port("state").unwrap().write(Arc::new(features.clone()));
+    c0.get_in_port("reward").unwrap().write(Arc::new(af::constant(1.0, unit)));
+    c1.get_in_port("state").unwrap().write(Arc::new(features));
+
+    let mut errors = Vec::new();
+
+    for _ in 0..3 {
+        c0.input();
+        c1.input();
+        c0.fire();
+        c1.fire();
+        c0.output();
+        c1.output();
+
+        let (r0, _) = af::sum_all(&c0.get_out_port("td_error").unwrap().read());
+        errors.push(r0);
+    }
+
+    assert_eq!(errors, vec![0.0, 1.0, 0.5]);
+
+    let (r1, _) = af::sum_all(&af::abs(c1.get_preferences()));
+    let (r2, _) = af::sum_all(&c1.get_out_port("policy").unwrap().read());
+
+    assert!(r1 > 0.0);
+    assert!((r2 - 1.0).abs() < 1e-9);
+}
@@ -0,0 +1,184 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+/// Adaptive exponential integrate-and-fire parameters in mV, ms, pF, nS and
+/// pA. The defaults are the cortical pyramidal cell fit of Brette and
+/// Gerstner (2005).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AdEx {
+    pub capacitance: f64,
+    pub leak: f64,
+    pub rest: f64,
+    pub threshold: f64,
+    pub slope: f64,
+    pub tau_w: f64,
+    pub a: f64,
+    pub b: f64,
+    pub reset: f64,
+    pub peak: f64,
+}
+
+impl Default for AdEx {
+    fn default() -> Self {
+        AdEx {
+            capacitance: 281.0,
+            leak: 30.0,
+            rest: -70.6,
+            threshold: -50.4,
+            slope: 2.0,
+            tau_w: 144.0,
+            a: 4.0,
+            b: 80.5,
+            reset: -70.6,
+            peak: 20.0,
+        }
+    }
+}
+
+/// Population of AdEx neurons. Each step integrates the current on "in"
+/// over `dt` in `substeps` Euler steps and writes 1 on "out" for every
+/// neuron that spiked during any of them, 0 otherwise.
+#[derive(Clone)]
+pub struct AdExPopulation {
+    base: ComponentStruct,
+    params: AdEx,
+    dt: f64,
+    substeps: u32,
+    potential: af::Array,
+    adaptation: af::Array,
+}
+
+impl AdExPopulation {
+    pub fn new(dims: af::Dim4, params: AdEx, dt: f64, substeps: u32) -> Self {
+        if substeps == 0 {
+            panic!("Number of substeps must be positive");
+        }
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", dims);
+        AdExPopulation {
+            base: base,
+            params: params,
+            dt: dt,
+            substeps: substeps,
+            potential: af::constant(params.rest, dims),
+            adaptation: af::constant(0.0, dims),
+        }
+    }
+
+    pub fn get_potential(&self) -> &af::Array {
+        &self.potential
+    }
+
+    pub fn get_adaptation(&self) -> &af::Array {
+        &self.adaptation
+    }
+
+    fn step(&mut self, current: &af::Array) -> af::Array {
+        let dims = self.potential.dims();
+        let k = |x: f64| af::constant(x, dims);
+        let p = self.params;
+        let h = self.dt / self.substeps as f64;
+
+        let mut fired = k(0.0);
+
+        for _ in 0..self.substeps {
+            let v = &self.potential;
+            let w = &self.adaptation;
+
+            let offset = v - &k(p.rest);
+            let onset = af::minof(&(&(v - &k(p.threshold)) / &k(p.slope)), &k(20.0), false);
+            let spike = &k(p.leak * p.slope) * &af::exp(&onset);
+            let leak = &k(p.leak) * &offset;
+            let dv = &(&(&(&spike - &leak) - w) + current) / &k(p.capacitance);
+            let dw = &(&(&k(p.a) * &offset) - w) / &k(p.tau_w);
+
+            let v = v + &(&dv * &k(h));
+            let w = w + &(&dw * &k(h));
+
+            let spikes = af::ge(&v, &k(p.peak), false).cast::<f64>();
+            let rest = &k(1.0) - &spikes;
+
+            self.potential = &(&v * &rest) + &(&k(p.reset) * &spikes);
+            self.adaptation = &w + &(&k(p.b) * &spikes);
+            fired = af::maxof(&fired, &spikes, false);
+        }
+
+        fired
+    }
+}
+
+impl Unit for AdExPopulation {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for AdExPopulation {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let current = self.base.get_input("in");
+        let value = self.step(&*current);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn adex_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let n_rows: u64 = 5;
+    let n_cols: u64 = 3;
+
+    let dims = af::Dim4::new(&[n_rows, n_cols, 1, 1]);
+
+    let mut c0 = AdExPopulation::new(dims, AdEx::default(), 1.0, 10);
+    let mut c1 = AdExPopulation::new(dims, AdEx::default(), 1.0, 10);
+
+    c0.get_in_port("in").unwrap().write(Arc::new(af::constant(1000.0, dims)));
+
+    let mut total = 0.0;
+
+    for _ in 0..100 {
+        c0.input();
+        c1.input();
+        c0.fire();
+        c1.fire();
+        c0.output();
+        c1.output();
+
+        let a0 = c0.get_out_port("out").unwrap().read();
+        let (r0, _) = af::sum_all(&a0);
+        total += r0;
+    }
+
+    let a1 = c1.get_out_port("out").unwrap().read();
+    let (r1, _) = af::sum_all(&a1);
+
+    assert!(total > 0.0);
+    assert_eq!(r1, 0.0);
+}
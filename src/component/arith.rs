@@ -0,0 +1,292 @@
+use port::Port;
+use unit::*;
+use component::*;
+use registry::{Registry, expect_args};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+/// Computes the shape of a binary operation between arrays of shape `a` and
+/// `b`. Each dimension must either match or be 1 in one of the operands, in
+/// which case that operand is tiled along it.
+pub fn broadcast(a: af::Dim4, b: af::Dim4) -> af::Dim4 {
+    let mut dims = [0; 4];
+    for i in 0..4 {
+        dims[i] = if a[i] == b[i] || b[i] == 1 {
+            a[i]
+        } else if a[i] == 1 {
+            b[i]
+        } else {
+            panic!("Mismatched operand dimensions (lhs: {} rhs: {})", a, b);
+        };
+    }
+    af::Dim4::new(&dims)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Max,
+    Min,
+}
+
+/// Applies `op` elementwise to "a" and "b", writing the result to "out".
+#[derive(Clone)]
+pub struct Binary {
+    base: ComponentStruct,
+    op: BinaryOp,
+}
+
+impl Binary {
+    pub fn new(op: BinaryOp, a: af::Dim4, b: af::Dim4) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("a", a);
+        base.make_in_port("b", b);
+        base.make_out_port("out", broadcast(a, b));
+        Binary {
+            base: base,
+            op: op,
+        }
+    }
+}
+
+impl Unit for Binary {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Binary {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let a = self.base.get_input("a");
+        let b = self.base.get_input("b");
+        let value = match self.op {
+            BinaryOp::Add => af::add(&*a, &*b, true),
+            BinaryOp::Sub => af::sub(&*a, &*b, true),
+            BinaryOp::Mul => af::mul(&*a, &*b, true),
+            BinaryOp::Div => af::div(&*a, &*b, true),
+            BinaryOp::Max => af::maxof(&*a, &*b, true),
+            BinaryOp::Min => af::minof(&*a, &*b, true),
+        };
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnaryOp {
+    Scale(f64),
+    Offset(f64),
+    Negate,
+    Abs,
+    Clip(f64, f64),
+    Exp,
+    Log,
+}
+
+/// Applies `op` elementwise to "in", writing the result to "out".
+#[derive(Clone)]
+pub struct Unary {
+    base: ComponentStruct,
+    dims: af::Dim4,
+    op: UnaryOp,
+}
+
+impl Unary {
+    pub fn new(op: UnaryOp, dims: af::Dim4) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", dims);
+        Unary {
+            base: base,
+            dims: dims,
+            op: op,
+        }
+    }
+}
+
+impl Unit for Unary {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Unary {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let dims = self.dims;
+        let value = match self.op {
+            UnaryOp::Scale(k) => af::mul(&*x, &af::constant(k, dims), false),
+            UnaryOp::Offset(k) => af::add(&*x, &af::constant(k, dims), false),
+            UnaryOp::Negate => af::mul(&*x, &af::constant(-1.0, dims), false),
+            UnaryOp::Abs => af::abs(&*x),
+            UnaryOp::Clip(lo, hi) => {
+                let upper = af::minof(&*x, &af::constant(hi, dims), false);
+                af::maxof(&upper, &af::constant(lo, dims), false)
+            },
+            UnaryOp::Exp => af::exp(&*x),
+            UnaryOp::Log => af::log(&*x),
+        };
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+/// Registers the binary operations as "add", "sub", "mul", "div", "max" and
+/// "min", built from the shapes of "a" and "b", and the unary ones as
+/// "scale", "offset", "negate", "abs", "clip", "exp" and "log", built from
+/// the shape of "in" and the operation's parameters in declaration order.
+pub fn register(registry: &mut Registry) {
+    let binary = [
+        ("add", BinaryOp::Add),
+        ("sub", BinaryOp::Sub),
+        ("mul", BinaryOp::Mul),
+        ("div", BinaryOp::Div),
+        ("max", BinaryOp::Max),
+        ("min", BinaryOp::Min),
+    ];
+    for &(name, op) in binary.iter() {
+        registry.register(name, move |dims, params| {
+            expect_args(name, dims, params, 2, 0);
+            Arc::new(Binary::new(op, dims[0], dims[1]))
+        });
+    }
+
+    let unary = [
+        ("scale", 1),
+        ("offset", 1),
+        ("negate", 0),
+        ("abs", 0),
+        ("clip", 2),
+        ("exp", 0),
+        ("log", 0),
+    ];
+    for &(name, n_params) in unary.iter() {
+        registry.register(name, move |dims, params| {
+            expect_args(name, dims, params, 1, n_params);
+            let op = match name {
+                "scale"  => UnaryOp::Scale(params[0]),
+                "offset" => UnaryOp::Offset(params[0]),
+                "negate" => UnaryOp::Negate,
+                "abs"    => UnaryOp::Abs,
+                "clip"   => UnaryOp::Clip(params[0], params[1]),
+                "exp"    => UnaryOp::Exp,
+                _        => UnaryOp::Log,
+            };
+            Arc::new(Unary::new(op, dims[0]))
+        });
+    }
+}
+
+#[test]
+fn arith_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let n_rows: u64 = 5;
+    let n_cols: u64 = 3;
+
+    let dims = af::Dim4::new(&[n_rows, n_cols, 1, 1]);
+    let row = af::Dim4::new(&[1, n_cols, 1, 1]);
+    let twos = af::constant(2.0, dims);
+    let threes = af::constant(3.0, row);
+
+    assert_eq!(broadcast(dims, row), dims);
+    assert_eq!(broadcast(row, dims), dims);
+
+    let mut c0 = Binary::new(BinaryOp::Mul, dims, row);
+    let mut c1 = Unary::new(UnaryOp::Clip(-4.0, 4.0), dims);
+
+    c0.get_in_port("a").unwrap().write(Arc::new(twos));
+    c0.get_in_port("b").unwrap().write(Arc::new(threes));
+    connect(&mut c1, "in", &mut c0, "out");
+
+    c0.input();
+    c1.input();
+    c0.fire();
+    c1.fire();
+    c0.output();
+    c1.output();
+
+    let a0 = c0.get_out_port("out").unwrap().read();
+    let (r0, _) = af::sum_all(&a0);
+
+    assert_eq!(r0, 90.0);
+
+    c1.input();
+    c1.fire();
+    c1.output();
+
+    let a1 = c1.get_out_port("out").unwrap().read();
+    let (r1, _) = af::sum_all(&a1);
+
+    assert_eq!(r1, 60.0);
+
+    let mut registry = Registry::new();
+    register(&mut registry);
+
+    assert_eq!(registry.get_names().len(), 13);
+
+    let mut c2 = registry.make("sub", &[dims, row], &[]);
+    let mut c3 = registry.make("scale", &[dims], &[0.5]);
+    let c2 = Arc::get_mut(&mut c2).unwrap();
+    let c3 = Arc::get_mut(&mut c3).unwrap();
+
+    c2.get_in_port("a").unwrap().write(Arc::new(af::constant(2.0, dims)));
+    c2.get_in_port("b").unwrap().write(Arc::new(af::constant(3.0, row)));
+    c2.input();
+    c2.fire();
+    c2.output();
+
+    let a2 = c2.get_out_port("out").unwrap().read();
+    c3.get_in_port("in").unwrap().write(a2);
+    c3.input();
+    c3.fire();
+    c3.output();
+
+    let (r2, _) = af::sum_all(&c3.get_out_port("out").unwrap().read());
+
+    assert_eq!(r2, -7.5);
+}
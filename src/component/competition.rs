@@ -0,0 +1,243 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Output {
+    OneHot,
+    Sparse,
+}
+
+/// Keeps the `k` largest elements of "in" and zeros the rest. With
+/// `Output::OneHot` the winners are written to "out" as ones, with
+/// `Output::Sparse` they keep their input values. Ties go to the lower
+/// index, and NaN ranks below every number.
+#[derive(Clone)]
+pub struct WinnerTakeAll {
+    base: ComponentStruct,
+    dims: af::Dim4,
+    k: usize,
+    output: Output,
+}
+
+impl WinnerTakeAll {
+    pub fn new(dims: af::Dim4, k: usize, output: Output) -> Self {
+        let n = (dims[0] * dims[1] * dims[2] * dims[3]) as usize;
+        if k == 0 || k > n {
+            panic!("Number of winners {} out of range (expected: 1..{})", k, n + 1);
+        }
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", dims);
+        WinnerTakeAll {
+            base: base,
+            dims: dims,
+            k: k,
+            output: output,
+        }
+    }
+
+    pub fn hard(dims: af::Dim4) -> Self {
+        WinnerTakeAll::new(dims, 1, Output::OneHot)
+    }
+
+    fn compete(&self, x: &af::Array) -> af::Array {
+        let mut values = vec![0.0; x.elements() as usize];
+        x.host(&mut values);
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        order.sort_by(|&a, &b| descending(values[a], values[b]).then(a.cmp(&b)));
+        let mut winners = vec![0.0; values.len()];
+        for &i in order.iter().take(self.k) {
+            winners[i] = match self.output {
+                Output::OneHot => 1.0,
+                Output::Sparse => values[i],
+            };
+        }
+        af::Array::new(&winners, self.dims)
+    }
+}
+
+fn descending(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => b.partial_cmp(&a).unwrap(),
+        (nan_a, nan_b) => nan_a.cmp(&nan_b),
+    }
+}
+
+impl Unit for WinnerTakeAll {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for WinnerTakeAll {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let value = self.compete(&*x);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+/// Rate units with mutual inhibition. Each step integrates
+/// `τ da/dt = -a + relu(x + w_self a - w_inh (Σa - a))` over `dt` from the
+/// input on "in" and writes the activity `a` to "out", so that repeated
+/// steps let the strongest inputs suppress the rest.
+#[derive(Clone)]
+pub struct LateralInhibition {
+    base: ComponentStruct,
+    tau: f64,
+    excitation: f64,
+    inhibition: f64,
+    dt: f64,
+    activity: af::Array,
+}
+
+impl LateralInhibition {
+    pub fn new(dims: af::Dim4, tau: f64, excitation: f64, inhibition: f64, dt: f64) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", dims);
+        LateralInhibition {
+            base: base,
+            tau: tau,
+            excitation: excitation,
+            inhibition: inhibition,
+            dt: dt,
+            activity: af::constant(0.0, dims),
+        }
+    }
+
+    pub fn get_activity(&self) -> &af::Array {
+        &self.activity
+    }
+
+    fn step(&mut self, x: &af::Array) -> af::Array {
+        let dims = self.activity.dims();
+        let k = |x: f64| af::constant(x, dims);
+        let a = &self.activity;
+        let (total, _) = af::sum_all(a);
+        let others = &k(total) - a;
+        let drive = &(x + &(a * &k(self.excitation))) - &(&others * &k(self.inhibition));
+        let target = af::maxof(&drive, &k(0.0), false);
+        let da = &(&target - a) * &k(self.dt / self.tau);
+        self.activity = a + &da;
+        self.activity.clone()
+    }
+}
+
+impl Unit for LateralInhibition {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for LateralInhibition {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let value = self.step(&*x);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn competition_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let dims = af::Dim4::new(&[4, 1, 1, 1]);
+    let x = af::Array::new(&[0.2, 0.9, 0.5, 0.9], dims);
+
+    let mut c0 = WinnerTakeAll::hard(dims);
+    let mut c1 = WinnerTakeAll::new(dims, 2, Output::Sparse);
+    let mut c2 = LateralInhibition::new(dims, 1.0, 0.0, 0.5, 0.5);
+
+    c0.get_in_port("in").unwrap().write(Arc::new(x.clone()));
+    c1.get_in_port("in").unwrap().write(Arc::new(x));
+    c2.get_in_port("in").unwrap().write(Arc::new(af::Array::new(&[1.0, 0.5, 0.0, 0.0], dims)));
+
+    for _ in 0..50 {
+        c0.input();
+        c1.input();
+        c2.input();
+        c0.fire();
+        c1.fire();
+        c2.fire();
+        c0.output();
+        c1.output();
+        c2.output();
+    }
+
+    let mut r0 = [0.0; 4];
+    let mut r1 = [0.0; 4];
+    let mut r2 = [0.0; 4];
+    c0.get_out_port("out").unwrap().read().host(&mut r0);
+    c1.get_out_port("out").unwrap().read().host(&mut r1);
+    c2.get_out_port("out").unwrap().read().host(&mut r2);
+
+    assert_eq!(r0, [0.0, 1.0, 0.0, 0.0]);
+    assert_eq!(r1, [0.0, 0.9, 0.0, 0.9]);
+    assert!(r2[0] > 0.5);
+    assert!(r2[1] < 0.2);
+
+    let (nan, inf) = (::std::f64::NAN, ::std::f64::INFINITY);
+
+    c0.get_in_port("in").unwrap().write(Arc::new(af::Array::new(&[nan, -1.0, -3.0, -2.0], dims)));
+    c1.get_in_port("in").unwrap().write(Arc::new(af::Array::new(&[nan, -inf, nan, -inf], dims)));
+    c0.input();
+    c1.input();
+    c0.fire();
+    c1.fire();
+    c0.output();
+    c1.output();
+
+    c0.get_out_port("out").unwrap().read().host(&mut r0);
+    c1.get_out_port("out").unwrap().read().host(&mut r1);
+
+    assert_eq!(r0, [0.0, 1.0, 0.0, 0.0]);
+    assert_eq!(r1, [0.0, -inf, 0.0, -inf]);
+}
@@ -1,7 +1,7 @@
 use port::Port;
 use unit::*;
 use component::*;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use af;
 
@@ -25,12 +25,12 @@ impl Unit for Constant {
         for base;
         fn make_in_port(&mut self, key: &str, dims: af::Dim4);
         fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
-        fn get_in_ports(&mut self) -> &mut HashMap<String, Port>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
         fn remove_in_port(&mut self, key: &str);
         fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
         fn make_out_port(&mut self, key: &str, dims: af::Dim4);
         fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
-        fn get_out_ports(&mut self) -> &mut HashMap<String, Port>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
         fn remove_out_port(&mut self, key: &str);
         fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
         fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
@@ -48,7 +48,7 @@ impl Component for Constant {
 
     fn fire(&mut self) {
         let value = self.value.clone();
-        let mut outputs = HashMap::<String, Arc<af::Array>>::new();
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
         outputs.insert("out".to_string(), Arc::new(value));
         self.base.outputs = outputs;
     }
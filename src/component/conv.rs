@@ -0,0 +1,319 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+fn span() -> af::Seq<f64> {
+    af::Seq::default()
+}
+
+fn strided(begin: u64, count: u64, step: u64) -> af::Seq<f64> {
+    let end = begin + (count - 1) * step;
+    af::Seq::new(begin as f64, end as f64, step as f64)
+}
+
+fn single(i: u64) -> af::Seq<f64> {
+    af::Seq::new(i as f64, i as f64, 1.0)
+}
+
+fn pad(x: &af::Array, dim: i32, size: u64) -> af::Array {
+    if size == 0 {
+        return x.clone();
+    }
+    let mut dims = x.dims();
+    dims[dim as usize] = size;
+    let zeros = af::constant(0.0, dims);
+    af::join(dim, &af::join(dim, &zeros, x), &zeros)
+}
+
+fn pooled(size: u64, window: u64, stride: u64) -> u64 {
+    if window == 0 || stride == 0 {
+        panic!("Window size and stride must be positive (window: {} stride: {})", window, stride);
+    }
+    if size < window {
+        panic!("Window size {} exceeds input size {}", window, size);
+    }
+    (size - window) / stride + 1
+}
+
+/// Convolves "in" (height × width × channels × batch) with a bank of
+/// kernels (kernel height × kernel width × in channels × out channels),
+/// keeping only the positions where the kernel lies entirely inside the
+/// padded input. As with `af::convolve2` this is true convolution, so the
+/// kernel is flipped along both axes; flip it beforehand to apply it as a
+/// cross-correlation template.
+#[derive(Clone)]
+pub struct Conv2D {
+    base: ComponentStruct,
+    kernels: af::Array,
+    stride: u64,
+    padding: u64,
+    out_dims: af::Dim4,
+}
+
+impl Conv2D {
+    pub fn new(dims: af::Dim4, kernels: af::Array, stride: u64, padding: u64) -> Self {
+        let k = kernels.dims();
+        if k[2] != dims[2] {
+            panic!("Mismatched channel count (expected: {} actual: {})", dims[2], k[2]);
+        }
+        if k[3] == 0 {
+            panic!("Kernel bank has no output channels");
+        }
+        let out_dims = af::Dim4::new(&[
+            pooled(dims[0] + 2 * padding, k[0], stride),
+            pooled(dims[1] + 2 * padding, k[1], stride),
+            k[3],
+            dims[3],
+        ]);
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", out_dims);
+        Conv2D {
+            base: base,
+            kernels: kernels,
+            stride: stride,
+            padding: padding,
+            out_dims: out_dims,
+        }
+    }
+
+    pub fn get_kernels(&self) -> &af::Array {
+        &self.kernels
+    }
+
+    pub fn set_kernels(&mut self, kernels: af::Array) {
+        if self.kernels.dims() != kernels.dims() {
+            panic!("Mismatched kernel dimensions (expected: {} actual: {})", self.kernels.dims(), kernels.dims());
+        }
+        self.kernels = kernels;
+    }
+
+    fn convolve(&self, x: &af::Array) -> af::Array {
+        let k = self.kernels.dims();
+        let padded = pad(&pad(x, 0, self.padding), 1, self.padding);
+        let rows = strided(k[0] - 1, self.out_dims[0], self.stride);
+        let cols = strided(k[1] - 1, self.out_dims[1], self.stride);
+        let mut maps: Option<af::Array> = None;
+        for o in 0..k[3] {
+            let mut sum = af::constant(0.0, af::Dim4::new(&[self.out_dims[0], self.out_dims[1], 1, self.out_dims[3]]));
+            for c in 0..k[2] {
+                let channel = af::index(&padded, &[span(), span(), single(c), span()]);
+                let kernel = af::index(&self.kernels, &[span(), span(), single(c), single(o)]);
+                let full = af::convolve2(&channel, &kernel, af::ConvMode::EXPAND, af::ConvDomain::AUTO);
+                let valid = af::index(&full, &[rows, cols, span(), span()]);
+                sum = af::add(&sum, &valid, false);
+            }
+            maps = Some(match maps {
+                Some(m) => af::join(2, &m, &sum),
+                None    => sum,
+            });
+        }
+        maps.unwrap()
+    }
+}
+
+impl Unit for Conv2D {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Conv2D {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let value = self.convolve(&*x);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pooling {
+    Max,
+    Avg,
+}
+
+/// Pools "in" (height × width × channels × batch) over `window` × `window`
+/// patches taken every `stride` pixels, independently for each channel.
+#[derive(Clone)]
+pub struct Pool2D {
+    base: ComponentStruct,
+    pooling: Pooling,
+    window: u64,
+    stride: u64,
+    out_dims: af::Dim4,
+}
+
+impl Pool2D {
+    pub fn new(pooling: Pooling, dims: af::Dim4, window: u64, stride: u64) -> Self {
+        let out_dims = af::Dim4::new(&[
+            pooled(dims[0], window, stride),
+            pooled(dims[1], window, stride),
+            dims[2],
+            dims[3],
+        ]);
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", out_dims);
+        Pool2D {
+            base: base,
+            pooling: pooling,
+            window: window,
+            stride: stride,
+            out_dims: out_dims,
+        }
+    }
+
+    pub fn max(dims: af::Dim4, window: u64, stride: u64) -> Self {
+        Pool2D::new(Pooling::Max, dims, window, stride)
+    }
+
+    pub fn avg(dims: af::Dim4, window: u64, stride: u64) -> Self {
+        Pool2D::new(Pooling::Avg, dims, window, stride)
+    }
+
+    fn pool(&self, x: &af::Array) -> af::Array {
+        let mut acc: Option<af::Array> = None;
+        for i in 0..self.window {
+            for j in 0..self.window {
+                let rows = strided(i, self.out_dims[0], self.stride);
+                let cols = strided(j, self.out_dims[1], self.stride);
+                let patch = af::index(x, &[rows, cols, span(), span()]);
+                acc = Some(match (acc, self.pooling) {
+                    (Some(a), Pooling::Max) => af::maxof(&a, &patch, false),
+                    (Some(a), Pooling::Avg) => af::add(&a, &patch, false),
+                    (None, _)               => patch,
+                });
+            }
+        }
+        let acc = acc.unwrap();
+        match self.pooling {
+            Pooling::Max => acc,
+            Pooling::Avg => {
+                let area = (self.window * self.window) as f64;
+                af::div(&acc, &af::constant(area, self.out_dims), false)
+            },
+        }
+    }
+}
+
+impl Unit for Pool2D {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Pool2D {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let value = self.pool(&*x);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn conv_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let dims = af::Dim4::new(&[6, 6, 2, 1]);
+    let ones = af::constant(1.0, dims);
+    let kernels = af::constant(1.0, af::Dim4::new(&[3, 3, 2, 4]));
+
+    let mut c0 = Conv2D::new(dims, kernels, 1, 0);
+    let mut c1 = Pool2D::max(af::Dim4::new(&[4, 4, 4, 1]), 2, 2);
+    let mut c2 = Pool2D::avg(af::Dim4::new(&[4, 4, 4, 1]), 2, 2);
+
+    connect(&mut c1, "in", &mut c0, "out");
+    connect(&mut c2, "in", &mut c0, "out");
+
+    c0.get_in_port("in").unwrap().write(Arc::new(ones));
+
+    for _ in 0..2 {
+        c0.input();
+        c1.input();
+        c2.input();
+        c0.fire();
+        c1.fire();
+        c2.fire();
+        c0.output();
+        c1.output();
+        c2.output();
+    }
+
+    let a0 = c0.get_out_port("out").unwrap().read();
+    let a1 = c1.get_out_port("out").unwrap().read();
+    let a2 = c2.get_out_port("out").unwrap().read();
+
+    assert_eq!(a0.dims(), af::Dim4::new(&[4, 4, 4, 1]));
+    assert_eq!(a1.dims(), af::Dim4::new(&[2, 2, 4, 1]));
+
+    let (r0, _) = af::sum_all(&a0);
+    let (r1, _) = af::sum_all(&a1);
+    let (r2, _) = af::sum_all(&a2);
+
+    assert_eq!(r0, 18.0 * 64.0);
+    assert_eq!(r1, 18.0 * 16.0);
+    assert_eq!(r2, 18.0 * 16.0);
+
+    let dims = af::Dim4::new(&[3, 3, 1, 1]);
+    let values: Vec<f64> = (1..10).map(|x| x as f64).collect();
+    let kernels = af::Array::new(&[1.0, 2.0, 3.0, 4.0], af::Dim4::new(&[2, 2, 1, 1]));
+
+    let mut c3 = Conv2D::new(dims, kernels, 1, 0);
+
+    c3.get_in_port("in").unwrap().write(Arc::new(af::Array::new(&values, dims)));
+    c3.input();
+    c3.fire();
+    c3.output();
+
+    let a3 = c3.get_out_port("out").unwrap().read();
+    let mut r3 = [0.0; 4];
+    a3.host(&mut r3);
+
+    assert_eq!(a3.dims(), af::Dim4::new(&[2, 2, 1, 1]));
+    assert_eq!(r3, [23.0, 33.0, 53.0, 63.0]);
+}
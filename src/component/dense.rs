@@ -0,0 +1,138 @@
+use port::Port;
+use unit::*;
+use component::*;
+use random::Stream;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Init {
+    Zeros,
+    Uniform(f64, f64),
+    Xavier,
+}
+
+pub fn initialize(init: Init, dims: af::Dim4, stream: &mut Stream) -> af::Array {
+    let (lo, hi) = match init {
+        Init::Zeros => return af::constant(0.0, dims),
+        Init::Uniform(lo, hi) => (lo, hi),
+        Init::Xavier => {
+            let limit = (6.0 / (dims[0] + dims[1]) as f64).sqrt();
+            (-limit, limit)
+        },
+    };
+    let draw = stream.randu(dims);
+    let scaled = af::mul(&draw, &af::constant(hi - lo, dims), false);
+    af::add(&scaled, &af::constant(lo, dims), false)
+}
+
+/// Computes `W·x + b` from "in" (`n_in` × 1) to "out" (`n_out` × 1).
+#[derive(Clone)]
+pub struct Dense {
+    base: ComponentStruct,
+    weights: af::Array,
+    bias: af::Array,
+}
+
+impl Dense {
+    pub fn new(n_in: u64, n_out: u64, init: Init, stream: &mut Stream) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", af::Dim4::new(&[n_in, 1, 1, 1]));
+        base.make_out_port("out", af::Dim4::new(&[n_out, 1, 1, 1]));
+        Dense {
+            base: base,
+            weights: initialize(init, af::Dim4::new(&[n_out, n_in, 1, 1]), stream),
+            bias: af::constant(0.0, af::Dim4::new(&[n_out, 1, 1, 1])),
+        }
+    }
+
+    pub fn get_weights(&self) -> &af::Array {
+        &self.weights
+    }
+
+    pub fn set_weights(&mut self, weights: af::Array) {
+        if self.weights.dims() != weights.dims() {
+            panic!("Mismatched weight dimensions (expected: {} actual: {})", self.weights.dims(), weights.dims());
+        }
+        self.weights = weights;
+    }
+
+    pub fn get_bias(&self) -> &af::Array {
+        &self.bias
+    }
+
+    pub fn set_bias(&mut self, bias: af::Array) {
+        if self.bias.dims() != bias.dims() {
+            panic!("Mismatched bias dimensions (expected: {} actual: {})", self.bias.dims(), bias.dims());
+        }
+        self.bias = bias;
+    }
+}
+
+impl Unit for Dense {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Dense {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let product = af::matmul(&self.weights, &*x, af::MatProp::NONE, af::MatProp::NONE);
+        let value = af::add(&product, &self.bias, false);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn dense_works() {
+    use random::Random;
+
+    af::set_backend(af::Backend::CPU);
+
+    let n_in: u64 = 3;
+    let n_out: u64 = 5;
+
+    let mut stream = Random::new(0).stream("dense");
+    let mut c0 = Dense::new(n_in, n_out, Init::Zeros, &mut stream);
+    let c1 = Dense::new(n_in, n_out, Init::Xavier, &mut stream);
+
+    let limit = (6.0 / 8.0 as f64).sqrt();
+    let (r0, _) = af::max_all(&af::abs(c1.get_weights()));
+
+    assert!(r0 <= limit);
+
+    c0.set_weights(af::constant(1.0, af::Dim4::new(&[n_out, n_in, 1, 1])));
+    c0.set_bias(af::constant(1.0, af::Dim4::new(&[n_out, 1, 1, 1])));
+    c0.get_in_port("in").unwrap().write(Arc::new(af::constant(2.0, af::Dim4::new(&[n_in, 1, 1, 1]))));
+    c0.input();
+    c0.fire();
+    c0.output();
+
+    let a0 = c0.get_out_port("out").unwrap().read();
+    let (r0, _) = af::sum_all(&a0);
+
+    assert_eq!(r0, 35.0);
+}
@@ -0,0 +1,256 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cell {
+    Empty,
+    Wall,
+    Goal,
+    Pit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rewards {
+    pub step: f64,
+    pub wall: f64,
+    pub goal: f64,
+    pub pit: f64,
+}
+
+impl Default for Rewards {
+    fn default() -> Self {
+        Rewards {
+            step: -0.01,
+            wall: -0.1,
+            goal: 1.0,
+            pit: -1.0,
+        }
+    }
+}
+
+/// Grid environment built from a text map in which `#` is a wall, `S` the
+/// start, `G` a goal, `X` a pit and `.` an empty cell. Each step reads an
+/// action index from "action" (0: up, 1: right, 2: down, 3: left), moves
+/// the agent unless a wall is in the way, and writes the cell index to
+/// "observation", a one-hot encoding of it to "features", and the reward
+/// and a done flag to "reward" and "done". Any other action value leaves the
+/// agent in place with the wall penalty. Goals and pits end the episode,
+/// and the step after that restarts from `S` without reading an action, as
+/// does the very first step.
+///
+/// When the agent is fired after the environment within a step, each action
+/// is applied on the following step to the observation it was chosen for.
+#[derive(Clone)]
+pub struct GridWorld {
+    base: ComponentStruct,
+    rows: usize,
+    cols: usize,
+    cells: Vec<Cell>,
+    start: usize,
+    rewards: Rewards,
+    position: usize,
+    done: bool,
+}
+
+impl GridWorld {
+    pub fn new(map: &str, rewards: Rewards) -> Self {
+        let lines: Vec<&str> = map.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).collect();
+        let rows = lines.len();
+        if rows == 0 {
+            panic!("Grid map is empty");
+        }
+        let cols = lines[0].chars().count();
+        let mut cells = vec![Cell::Empty; rows * cols];
+        let mut start = None;
+        for (r, line) in lines.iter().enumerate() {
+            if line.chars().count() != cols {
+                panic!("Grid map row {} has {} cells (expected: {})", r, line.chars().count(), cols);
+            }
+            for (c, ch) in line.chars().enumerate() {
+                let index = r + c * rows;
+                cells[index] = match ch {
+                    '.' => Cell::Empty,
+                    '#' => Cell::Wall,
+                    'G' => Cell::Goal,
+                    'X' => Cell::Pit,
+                    'S' => {
+                        if start.is_some() {
+                            panic!("Grid map has more than one start");
+                        }
+                        start = Some(index);
+                        Cell::Empty
+                    },
+                    _ => panic!("Unknown grid map cell `{}`", ch),
+                };
+            }
+        }
+        let start = match start {
+            Some(x) => x,
+            None    => panic!("Grid map has no start"),
+        };
+
+        let unit = af::Dim4::new(&[1, 1, 1, 1]);
+        let mut base = ComponentStruct::new();
+        base.make_in_port("action", unit);
+        base.make_out_port("observation", unit);
+        base.make_out_port("features", af::Dim4::new(&[(rows * cols) as u64, 1, 1, 1]));
+        base.make_out_port("reward", unit);
+        base.make_out_port("done", unit);
+        GridWorld {
+            base: base,
+            rows: rows,
+            cols: cols,
+            cells: cells,
+            start: start,
+            rewards: rewards,
+            position: start,
+            done: true,
+        }
+    }
+
+    pub fn count_states(&self) -> u64 {
+        (self.rows * self.cols) as u64
+    }
+
+    pub fn get_position(&self) -> (usize, usize) {
+        (self.position % self.rows, self.position / self.rows)
+    }
+
+    fn step(&mut self, action: Option<usize>) -> (f64, bool) {
+        if self.done {
+            self.position = self.start;
+            self.done = false;
+            return (0.0, false);
+        }
+        let (r, c) = self.get_position();
+        let (r, c) = match action {
+            Some(0) if r > 0             => (r - 1, c),
+            Some(1) if c + 1 < self.cols => (r, c + 1),
+            Some(2) if r + 1 < self.rows => (r + 1, c),
+            Some(3) if c > 0             => (r, c - 1),
+            _                            => return (self.rewards.wall, false),
+        };
+        let next = r + c * self.rows;
+        let (reward, done) = match self.cells[next] {
+            Cell::Wall  => return (self.rewards.wall, false),
+            Cell::Empty => (self.rewards.step, false),
+            Cell::Goal  => (self.rewards.goal, true),
+            Cell::Pit   => (self.rewards.pit, true),
+        };
+        self.position = next;
+        self.done = done;
+        (reward, done)
+    }
+}
+
+impl Unit for GridWorld {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for GridWorld {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let action = select(&*self.base.get_input("action"), 4);
+        let (reward, done) = self.step(action);
+        let unit = af::Dim4::new(&[1, 1, 1, 1]);
+        let mut features = vec![0.0; self.rows * self.cols];
+        features[self.position] = 1.0;
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("observation".to_string(), Arc::new(af::constant(self.position as f64, unit)));
+        outputs.insert("features".to_string(), Arc::new(af::Array::new(&features, af::Dim4::new(&[features.len() as u64, 1, 1, 1]))));
+        outputs.insert("reward".to_string(), Arc::new(af::constant(reward, unit)));
+        outputs.insert("done".to_string(), Arc::new(af::constant(if done { 1.0 } else { 0.0 }, unit)));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn gridworld_works() {
+    use component::tabular::{TabularAgent, Exploration};
+    use random::Random;
+
+    af::set_backend(af::Backend::CPU);
+
+    let map = "
+        #####
+        #S.G#
+        #.#X#
+        #####
+    ";
+
+    let mut c0 = GridWorld::new(map, Rewards::default());
+
+    assert_eq!(c0.count_states(), 20);
+
+    let mut rewards = Vec::new();
+
+    for &action in [0, 0, -1, 4, 1, 1].iter() {
+        c0.get_in_port("action").unwrap().write(Arc::new(af::constant(action as f64, af::Dim4::new(&[1, 1, 1, 1]))));
+        c0.input();
+        c0.fire();
+        c0.output();
+
+        let (r0, _) = af::sum_all(&c0.get_out_port("reward").unwrap().read());
+        rewards.push(r0);
+    }
+
+    let (r1, _) = af::sum_all(&c0.get_out_port("done").unwrap().read());
+
+    assert_eq!(c0.get_position(), (1, 3));
+    assert_eq!(rewards, vec![0.0, -0.1, -0.1, -0.1, -0.01, 1.0]);
+    assert_eq!(r1, 1.0);
+
+    let mut c0 = GridWorld::new(map, Rewards::default());
+    let mut c1 = TabularAgent::q_learning(c0.count_states(), 4, 0.5, 0.9, Exploration::EpsilonGreedy(0.2), Random::new(0).stream("agent"));
+
+    connect(&mut c1, "state", &mut c0, "observation");
+    connect(&mut c1, "reward", &mut c0, "reward");
+    connect(&mut c1, "done", &mut c0, "done");
+    connect(&mut c0, "action", &mut c1, "action");
+
+    let mut goals = 0;
+
+    for _ in 0..500 {
+        c0.input();
+        c0.fire();
+        c0.output();
+        c1.input();
+        c1.fire();
+        c1.output();
+
+        let (r0, _) = af::sum_all(&c0.get_out_port("reward").unwrap().read());
+        if r0 == 1.0 {
+            goals += 1;
+        }
+    }
+
+    let mut table = vec![0.0; 20 * 4];
+    c1.get_table().host(&mut table);
+
+    assert!(goals > 10);
+    assert!(table[5 + 3 * 20] < table[5 + 1 * 20]);
+}
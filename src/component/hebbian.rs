@@ -0,0 +1,193 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    /// `dW = η y xᵀ`
+    Hebb,
+    /// `dW = η (y xᵀ - y² W)`, which keeps each row of `W` near unit norm.
+    Oja,
+    /// `dW = η y (y - θ) xᵀ`, where the threshold `θ` tracks `y²` with time
+    /// constant `tau`.
+    BCM { tau: f64 },
+}
+
+/// Learns an `n_post` × `n_pre` weight matrix from activity on "pre" and
+/// "post" each step. The 1 × 1 "rate" port scales every update, so a
+/// neuromodulatory signal connected to it gates learning, and the current
+/// weights are written to "weights".
+#[derive(Clone)]
+pub struct Hebbian {
+    base: ComponentStruct,
+    rule: Rule,
+    dt: f64,
+    weights: af::Array,
+    theta: af::Array,
+}
+
+impl Hebbian {
+    pub fn new(rule: Rule, weights: af::Array, dt: f64) -> Self {
+        let dims = weights.dims();
+        let mut base = ComponentStruct::new();
+        base.make_in_port("pre", af::Dim4::new(&[dims[1], 1, 1, 1]));
+        base.make_in_port("post", af::Dim4::new(&[dims[0], 1, 1, 1]));
+        base.make_in_port("rate", af::Dim4::new(&[1, 1, 1, 1]));
+        base.make_out_port("weights", dims);
+        Hebbian {
+            base: base,
+            rule: rule,
+            dt: dt,
+            weights: weights,
+            theta: af::constant(0.0, af::Dim4::new(&[dims[0], 1, 1, 1])),
+        }
+    }
+
+    pub fn get_weights(&self) -> &af::Array {
+        &self.weights
+    }
+
+    pub fn set_weights(&mut self, weights: af::Array) {
+        if self.weights.dims() != weights.dims() {
+            panic!("Mismatched weight dimensions (expected: {} actual: {})", self.weights.dims(), weights.dims());
+        }
+        self.weights = weights;
+    }
+
+    pub fn get_threshold(&self) -> &af::Array {
+        &self.theta
+    }
+
+    fn learn(&mut self, x: &af::Array, y: &af::Array, rate: &af::Array) {
+        let outer = |a: &af::Array, b: &af::Array| af::matmul(a, b, af::MatProp::NONE, af::MatProp::TRANS);
+        let dw = match self.rule {
+            Rule::Hebb => outer(y, x),
+            Rule::Oja => {
+                let decay = af::mul(&(y * y), &self.weights, true);
+                &outer(y, x) - &decay
+            },
+            Rule::BCM { tau } => {
+                let dims = y.dims();
+                let k = af::constant(self.dt / tau, dims);
+                self.theta = &self.theta + &(&(&(y * y) - &self.theta) * &k);
+                outer(&(y * &(y - &self.theta)), x)
+            },
+        };
+        self.weights = &self.weights + &af::mul(&dw, rate, true);
+    }
+}
+
+impl Unit for Hebbian {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Hebbian {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("pre");
+        let y = self.base.get_input("post");
+        let rate = self.base.get_input("rate");
+        self.learn(&*x, &*y, &*rate);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("weights".to_string(), Arc::new(self.weights.clone()));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn hebbian_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let n_pre: u64 = 3;
+    let n_post: u64 = 2;
+
+    let dims = af::Dim4::new(&[n_post, n_pre, 1, 1]);
+    let pre = af::constant(1.0, af::Dim4::new(&[n_pre, 1, 1, 1]));
+    let post = af::constant(1.0, af::Dim4::new(&[n_post, 1, 1, 1]));
+    let unit = af::Dim4::new(&[1, 1, 1, 1]);
+
+    let mut c0 = Hebbian::new(Rule::Hebb, af::constant(0.0, dims), 1.0);
+    let mut c1 = Hebbian::new(Rule::Oja, af::constant(1.0, dims), 1.0);
+    let mut c2 = Hebbian::new(Rule::Hebb, af::constant(0.0, dims), 1.0);
+
+    for c in [&mut c0, &mut c1, &mut c2].iter_mut() {
+        c.get_in_port("pre").unwrap().write(Arc::new(pre.clone()));
+        c.get_in_port("post").unwrap().write(Arc::new(post.clone()));
+    }
+
+    c0.get_in_port("rate").unwrap().write(Arc::new(af::constant(0.5, unit)));
+    c1.get_in_port("rate").unwrap().write(Arc::new(af::constant(0.5, unit)));
+
+    c0.input();
+    c1.input();
+    c2.input();
+    c0.fire();
+    c1.fire();
+    c2.fire();
+    c0.output();
+    c1.output();
+    c2.output();
+
+    let a0 = c0.get_out_port("weights").unwrap().read();
+    let a1 = c1.get_out_port("weights").unwrap().read();
+    let a2 = c2.get_out_port("weights").unwrap().read();
+    let (r0, _) = af::sum_all(&a0);
+    let (r1, _) = af::sum_all(&a1);
+    let (r2, _) = af::sum_all(&a2);
+
+    assert_eq!(r0, 3.0);
+    assert_eq!(r1, 6.0);
+    assert_eq!(r2, 0.0);
+
+    let scalar = |x: f64| Arc::new(af::constant(x, unit));
+    let mut c3 = Hebbian::new(Rule::BCM { tau: 10.0 }, af::constant(0.0, unit), 1.0);
+
+    c3.get_in_port("pre").unwrap().write(scalar(1.0));
+    c3.get_in_port("post").unwrap().write(scalar(0.5));
+    c3.get_in_port("rate").unwrap().write(scalar(1.0));
+
+    let mut changes = Vec::new();
+
+    for t in 0..201 {
+        if t == 200 {
+            c3.get_in_port("post").unwrap().write(scalar(0.2));
+        }
+        let (w0, _) = af::sum_all(c3.get_weights());
+        c3.input();
+        c3.fire();
+        c3.output();
+        let (w1, _) = af::sum_all(c3.get_weights());
+        changes.push(w1 - w0);
+
+        if t == 199 {
+            let (r3, _) = af::sum_all(c3.get_threshold());
+            assert!((r3 - 0.25).abs() < 1e-6);
+        }
+    }
+
+    assert!(changes[199] > 0.0);
+    assert!(changes[200] < 0.0);
+}
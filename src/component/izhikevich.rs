@@ -0,0 +1,176 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Izhikevich {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub peak: f64,
+}
+
+impl Izhikevich {
+    pub fn regular_spiking() -> Self {
+        Izhikevich { a: 0.02, b: 0.2, c: -65.0, d: 8.0, peak: 30.0 }
+    }
+
+    pub fn intrinsically_bursting() -> Self {
+        Izhikevich { a: 0.02, b: 0.2, c: -55.0, d: 4.0, peak: 30.0 }
+    }
+
+    pub fn chattering() -> Self {
+        Izhikevich { a: 0.02, b: 0.2, c: -50.0, d: 2.0, peak: 30.0 }
+    }
+}
+
+impl Default for Izhikevich {
+    fn default() -> Self {
+        Izhikevich::regular_spiking()
+    }
+}
+
+/// Population of Izhikevich neurons. Each step integrates the current on
+/// "in" over `dt` in `substeps` Euler steps and writes 1 on "out" for every
+/// neuron that spiked during any of them, 0 otherwise.
+#[derive(Clone)]
+pub struct IzhikevichPopulation {
+    base: ComponentStruct,
+    params: Izhikevich,
+    dt: f64,
+    substeps: u32,
+    potential: af::Array,
+    recovery: af::Array,
+}
+
+impl IzhikevichPopulation {
+    pub fn new(dims: af::Dim4, params: Izhikevich, dt: f64, substeps: u32) -> Self {
+        if substeps == 0 {
+            panic!("Number of substeps must be positive");
+        }
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", dims);
+        IzhikevichPopulation {
+            base: base,
+            params: params,
+            dt: dt,
+            substeps: substeps,
+            potential: af::constant(params.c, dims),
+            recovery: af::constant(params.b * params.c, dims),
+        }
+    }
+
+    pub fn get_potential(&self) -> &af::Array {
+        &self.potential
+    }
+
+    pub fn get_recovery(&self) -> &af::Array {
+        &self.recovery
+    }
+
+    fn step(&mut self, current: &af::Array) -> af::Array {
+        let dims = self.potential.dims();
+        let k = |x: f64| af::constant(x, dims);
+        let p = self.params;
+        let h = self.dt / self.substeps as f64;
+
+        let mut fired = k(0.0);
+
+        for _ in 0..self.substeps {
+            let v = &self.potential;
+            let u = &self.recovery;
+
+            let quadratic = &(&(&k(0.04) * &(v * v)) + &(&k(5.0) * v)) + &k(140.0);
+            let dv = &(&quadratic - u) + current;
+            let du = &k(p.a) * &(&(&k(p.b) * v) - u);
+
+            let v = v + &(&dv * &k(h));
+            let u = u + &(&du * &k(h));
+
+            let spikes = af::ge(&v, &k(p.peak), false).cast::<f64>();
+            let rest = &k(1.0) - &spikes;
+
+            self.potential = &(&v * &rest) + &(&k(p.c) * &spikes);
+            self.recovery = &u + &(&k(p.d) * &spikes);
+            fired = af::maxof(&fired, &spikes, false);
+        }
+
+        fired
+    }
+}
+
+impl Unit for IzhikevichPopulation {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for IzhikevichPopulation {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let current = self.base.get_input("in");
+        let value = self.step(&*current);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn izhikevich_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let n_rows: u64 = 5;
+    let n_cols: u64 = 3;
+
+    let dims = af::Dim4::new(&[n_rows, n_cols, 1, 1]);
+
+    let mut c0 = IzhikevichPopulation::new(dims, Izhikevich::regular_spiking(), 1.0, 4);
+    let mut c1 = IzhikevichPopulation::new(dims, Izhikevich::intrinsically_bursting(), 1.0, 4);
+
+    c0.get_in_port("in").unwrap().write(Arc::new(af::constant(10.0, dims)));
+
+    let mut total = 0.0;
+
+    for _ in 0..100 {
+        c0.input();
+        c1.input();
+        c0.fire();
+        c1.fire();
+        c0.output();
+        c1.output();
+
+        let a0 = c0.get_out_port("out").unwrap().read();
+        let (r0, _) = af::sum_all(&a0);
+        total += r0;
+    }
+
+    let a1 = c1.get_out_port("out").unwrap().read();
+    let (r1, _) = af::sum_all(&a1);
+
+    assert!(total > 0.0);
+    assert_eq!(r1, 0.0);
+}
@@ -0,0 +1,244 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+fn mm(a: &af::Array, b: &af::Array) -> af::Array {
+    af::matmul(a, b, af::MatProp::NONE, af::MatProp::NONE)
+}
+
+fn mmt(a: &af::Array, b: &af::Array) -> af::Array {
+    af::matmul(a, b, af::MatProp::NONE, af::MatProp::TRANS)
+}
+
+fn check_square(name: &str, x: &af::Array, n: u64) {
+    let dims = x.dims();
+    if dims[0] != n || dims[1] != n {
+        panic!("{} must be {} x {} (actual: {})", name, n, n, dims);
+    }
+}
+
+/// Linear Kalman filter with state transition `F`, observation `H`, process
+/// noise `Q` and measurement noise `R`. Each step predicts from the previous
+/// estimate, corrects it with the observation on "observation" (`m` × 1),
+/// and writes the state estimate (`n` × 1) and its covariance (`n` × `n`)
+/// to "state" and "covariance".
+#[derive(Clone)]
+pub struct KalmanFilter {
+    base: ComponentStruct,
+    transition: af::Array,
+    observation: af::Array,
+    process_noise: af::Array,
+    measurement_noise: af::Array,
+    control: Option<af::Array>,
+    state: af::Array,
+    covariance: af::Array,
+}
+
+impl KalmanFilter {
+    pub fn new(transition: af::Array, observation: af::Array, process_noise: af::Array, measurement_noise: af::Array) -> Self {
+        let n = transition.dims()[0];
+        let m = observation.dims()[0];
+        check_square("State transition", &transition, n);
+        check_square("Process noise", &process_noise, n);
+        check_square("Measurement noise", &measurement_noise, m);
+        if observation.dims()[1] != n {
+            panic!("Observation matrix must be {} x {} (actual: {})", m, n, observation.dims());
+        }
+        let mut base = ComponentStruct::new();
+        base.make_in_port("observation", af::Dim4::new(&[m, 1, 1, 1]));
+        base.make_out_port("state", af::Dim4::new(&[n, 1, 1, 1]));
+        base.make_out_port("covariance", af::Dim4::new(&[n, n, 1, 1]));
+        KalmanFilter {
+            base: base,
+            transition: transition,
+            observation: observation,
+            process_noise: process_noise,
+            measurement_noise: measurement_noise,
+            control: None,
+            state: af::constant(0.0, af::Dim4::new(&[n, 1, 1, 1])),
+            covariance: af::diag_create(&af::constant(1.0, af::Dim4::new(&[n, 1, 1, 1])), 0),
+        }
+    }
+
+    /// Adds a "control" port (`p` × 1) whose value enters the prediction
+    /// through the `n` × `p` control matrix `B`.
+    pub fn with_control(mut self, control: af::Array) -> Self {
+        let n = self.transition.dims()[0];
+        if control.dims()[0] != n {
+            panic!("Control matrix must have {} rows (actual: {})", n, control.dims());
+        }
+        self.base.make_in_port("control", af::Dim4::new(&[control.dims()[1], 1, 1, 1]));
+        self.control = Some(control);
+        self
+    }
+
+    pub fn with_initial(mut self, state: af::Array, covariance: af::Array) -> Self {
+        if state.dims() != self.state.dims() || covariance.dims() != self.covariance.dims() {
+            panic!("Mismatched initial estimate dimensions (state: {} covariance: {})", state.dims(), covariance.dims());
+        }
+        self.state = state;
+        self.covariance = covariance;
+        self
+    }
+
+    pub fn get_state(&self) -> &af::Array {
+        &self.state
+    }
+
+    pub fn get_covariance(&self) -> &af::Array {
+        &self.covariance
+    }
+
+    fn step(&mut self, z: &af::Array, u: Option<&af::Array>) {
+        let f = &self.transition;
+        let h = &self.observation;
+
+        let mut x = mm(f, &self.state);
+        if let (Some(b), Some(u)) = (self.control.as_ref(), u) {
+            x = &x + &mm(b, u);
+        }
+        let p = &mmt(&mm(f, &self.covariance), f) + &self.process_noise;
+
+        let innovation = z - &mm(h, &x);
+        let s = &mmt(&mm(h, &p), h) + &self.measurement_noise;
+        let gain = af::transpose(&af::solve(&s, &mm(h, &p), af::MatProp::NONE), false);
+
+        let n = x.dims()[0];
+        let identity = af::diag_create(&af::constant(1.0, af::Dim4::new(&[n, 1, 1, 1])), 0);
+        self.state = &x + &mm(&gain, &innovation);
+        self.covariance = mm(&(&identity - &mm(&gain, h)), &p);
+    }
+}
+
+impl Unit for KalmanFilter {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for KalmanFilter {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let z = self.base.get_input("observation");
+        let u = match self.control {
+            Some(_) => Some(self.base.get_input("control")),
+            None    => None,
+        };
+        self.step(&*z, u.as_ref().map(|x| &**x));
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("state".to_string(), Arc::new(self.state.clone()));
+        outputs.insert("covariance".to_string(), Arc::new(self.covariance.clone()));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn kalman_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let unit = af::Dim4::new(&[1, 1, 1, 1]);
+
+    let mut c0 = KalmanFilter::new(
+        af::constant(1.0, unit),
+        af::constant(1.0, unit),
+        af::constant(0.0, unit),
+        af::constant(1.0, unit),
+    );
+    let mut c1 = KalmanFilter::new(
+        af::constant(1.0, unit),
+        af::constant(1.0, unit),
+        af::constant(0.0, unit),
+        af::constant(1.0, unit),
+    ).with_control(af::constant(1.0, unit));
+
+    c0.get_in_port("observation").unwrap().write(Arc::new(af::constant(4.0, unit)));
+    c1.get_in_port("observation").unwrap().write(Arc::new(af::constant(4.0, unit)));
+    c1.get_in_port("control").unwrap().write(Arc::new(af::constant(2.0, unit)));
+
+    c0.input();
+    c1.input();
+    c0.fire();
+    c1.fire();
+    c0.output();
+    c1.output();
+
+    let (r0, _) = af::sum_all(&c0.get_out_port("state").unwrap().read());
+    let (r1, _) = af::sum_all(&c0.get_out_port("covariance").unwrap().read());
+    let (r2, _) = af::sum_all(&c1.get_out_port("state").unwrap().read());
+
+    assert!((r0 - 2.0).abs() < 1e-9);
+    assert!((r1 - 0.5).abs() < 1e-9);
+    assert!((r2 - 3.0).abs() < 1e-9);
+
+    for _ in 0..100 {
+        c0.input();
+        c0.fire();
+        c0.output();
+    }
+
+    let (r0, _) = af::sum_all(&c0.get_out_port("state").unwrap().read());
+
+    assert!((r0 - 4.0).abs() < 0.1);
+
+    let transition = af::Array::new(&[1.0, 0.0, 1.0, 1.0], af::Dim4::new(&[2, 2, 1, 1]));
+    let observation = af::Array::new(&[1.0, 0.0], af::Dim4::new(&[1, 2, 1, 1]));
+    let control = af::Array::new(&[0.5, 1.0], af::Dim4::new(&[2, 1, 1, 1]));
+
+    let mut c2 = KalmanFilter::new(
+        transition.clone(),
+        observation.clone(),
+        af::constant(0.0, af::Dim4::new(&[2, 2, 1, 1])),
+        af::constant(1.0, unit),
+    );
+    let mut c3 = KalmanFilter::new(
+        transition,
+        observation,
+        af::constant(0.0, af::Dim4::new(&[2, 2, 1, 1])),
+        af::constant(1.0, unit),
+    ).with_control(control);
+
+    c2.get_in_port("observation").unwrap().write(Arc::new(af::constant(3.0, unit)));
+    c3.get_in_port("observation").unwrap().write(Arc::new(af::constant(3.0, unit)));
+    c3.get_in_port("control").unwrap().write(Arc::new(af::constant(2.0, unit)));
+
+    c2.input();
+    c3.input();
+    c2.fire();
+    c3.fire();
+    c2.output();
+    c3.output();
+
+    let mut r0 = [0.0; 2];
+    let mut r1 = [0.0; 4];
+    let mut r2 = [0.0; 2];
+    c2.get_out_port("state").unwrap().read().host(&mut r0);
+    c2.get_out_port("covariance").unwrap().read().host(&mut r1);
+    c3.get_out_port("state").unwrap().read().host(&mut r2);
+
+    let close = |a: &[f64], b: &[f64]| a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-9);
+
+    assert!(close(&r0, &[2.0, 1.0]));
+    assert!(close(&r1, &[2.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0]));
+    assert!(close(&r2, &[7.0 / 3.0, 8.0 / 3.0]));
+}
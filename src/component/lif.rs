@@ -0,0 +1,157 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LIF {
+    pub tau: f64,
+    pub rest: f64,
+    pub resistance: f64,
+    pub threshold: f64,
+    pub reset: f64,
+    pub refractory: f64,
+}
+
+impl Default for LIF {
+    fn default() -> Self {
+        LIF {
+            tau: 20.0,
+            rest: -65.0,
+            resistance: 1.0,
+            threshold: -50.0,
+            reset: -65.0,
+            refractory: 2.0,
+        }
+    }
+}
+
+/// Population of leaky integrate-and-fire neurons. Each step integrates the
+/// current on "in" over `dt` and writes 1 on "out" for every neuron whose
+/// membrane potential crossed threshold, 0 otherwise.
+#[derive(Clone)]
+pub struct LIFPopulation {
+    base: ComponentStruct,
+    params: LIF,
+    dt: f64,
+    potential: af::Array,
+    refractory: af::Array,
+}
+
+impl LIFPopulation {
+    pub fn new(dims: af::Dim4, params: LIF, dt: f64) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", dims);
+        LIFPopulation {
+            base: base,
+            params: params,
+            dt: dt,
+            potential: af::constant(params.rest, dims),
+            refractory: af::constant(0.0, dims),
+        }
+    }
+
+    pub fn get_potential(&self) -> &af::Array {
+        &self.potential
+    }
+
+    pub fn set_potential(&mut self, potential: af::Array) {
+        if self.potential.dims() != potential.dims() {
+            panic!("Mismatched potential dimensions (expected: {} actual: {})", self.potential.dims(), potential.dims());
+        }
+        self.potential = potential;
+    }
+
+    fn step(&mut self, current: &af::Array) -> af::Array {
+        let dims = self.potential.dims();
+        let k = |x: f64| af::constant(x, dims);
+        let p = self.params;
+
+        let active = af::le(&self.refractory, &k(0.0), false).cast::<f64>();
+        let drive = &(&k(p.rest) - &self.potential) + &(current * &k(p.resistance));
+        let dv = &drive * &k(self.dt / p.tau);
+        let v = &self.potential + &(&dv * &active);
+
+        let spikes = af::ge(&v, &k(p.threshold), false).cast::<f64>();
+        let rest = &k(1.0) - &spikes;
+        let countdown = af::maxof(&(&self.refractory - &k(self.dt)), &k(0.0), false);
+
+        self.potential = &(&v * &rest) + &(&k(p.reset) * &spikes);
+        self.refractory = &(&countdown * &rest) + &(&k(p.refractory) * &spikes);
+        spikes
+    }
+}
+
+impl Unit for LIFPopulation {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for LIFPopulation {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let current = self.base.get_input("in");
+        let value = self.step(&*current);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn lif_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let n_rows: u64 = 5;
+    let n_cols: u64 = 3;
+
+    let dims = af::Dim4::new(&[n_rows, n_cols, 1, 1]);
+    let params = LIF {
+        tau: 1.0,
+        rest: 0.0,
+        resistance: 1.0,
+        threshold: 1.0,
+        reset: 0.0,
+        refractory: 1.5,
+    };
+
+    let mut c0 = LIFPopulation::new(dims, params, 1.0);
+
+    c0.get_in_port("in").unwrap().write(Arc::new(af::constant(2.0, dims)));
+
+    let mut counts = Vec::new();
+
+    for _ in 0..4 {
+        c0.input();
+        c0.fire();
+        c0.output();
+
+        let a0 = c0.get_out_port("out").unwrap().read();
+        let (r0, _) = af::sum_all(&a0);
+        counts.push(r0);
+    }
+
+    assert_eq!(counts, vec![15.0, 0.0, 0.0, 15.0]);
+}
@@ -0,0 +1,201 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+/// Writes the value "in" had on the previous step to "out". The first step
+/// emits `initial`, which defaults to zeros.
+#[derive(Clone)]
+pub struct UnitDelay {
+    base: ComponentStruct,
+    state: af::Array,
+}
+
+impl UnitDelay {
+    pub fn new(dims: af::Dim4) -> Self {
+        UnitDelay::with_initial(af::constant(0.0, dims))
+    }
+
+    pub fn with_initial(initial: af::Array) -> Self {
+        let dims = initial.dims();
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", dims);
+        UnitDelay {
+            base: base,
+            state: initial,
+        }
+    }
+}
+
+impl Unit for UnitDelay {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for UnitDelay {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let value = self.state.clone();
+        self.state = (*x).clone();
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+/// Accumulates "in" into a state that decays by `decay` each step before
+/// the new input is added, writing the result to "out". Elements where
+/// "reset" is nonzero are cleared before accumulating.
+#[derive(Clone)]
+pub struct Integrator {
+    base: ComponentStruct,
+    decay: f64,
+    state: af::Array,
+}
+
+impl Integrator {
+    pub fn new(dims: af::Dim4) -> Self {
+        Integrator::with_decay(dims, 1.0)
+    }
+
+    pub fn with_decay(dims: af::Dim4, decay: f64) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_in_port("reset", dims);
+        base.make_out_port("out", dims);
+        Integrator {
+            base: base,
+            decay: decay,
+            state: af::constant(0.0, dims),
+        }
+    }
+
+    /// Builds a leaky integrator whose state decays as `exp(-dt / tau)`.
+    pub fn leaky(dims: af::Dim4, tau: f64, dt: f64) -> Self {
+        Integrator::with_decay(dims, (-dt / tau).exp())
+    }
+
+    pub fn get_state(&self) -> &af::Array {
+        &self.state
+    }
+
+    fn step(&mut self, x: &af::Array, reset: &af::Array) -> af::Array {
+        let dims = self.state.dims();
+        let keep = af::eq(reset, &af::constant(0.0, dims), false).cast::<f64>();
+        let decayed = &self.state * &af::constant(self.decay, dims);
+        self.state = &(&decayed * &keep) + x;
+        self.state.clone()
+    }
+}
+
+impl Unit for Integrator {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Integrator {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let reset = self.base.get_input("reset");
+        let value = self.step(&*x, &*reset);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn memory_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let n_rows: u64 = 5;
+    let n_cols: u64 = 3;
+
+    let dims = af::Dim4::new(&[n_rows, n_cols, 1, 1]);
+    let ones = af::constant(1.0, dims);
+
+    let mut c0 = UnitDelay::new(dims);
+    let mut c1 = Integrator::new(dims);
+    let mut c2 = Integrator::leaky(dims, 1.0, (2.0 as f64).ln());
+
+    c0.get_in_port("in").unwrap().write(Arc::new(ones.clone()));
+    c1.get_in_port("in").unwrap().write(Arc::new(ones.clone()));
+    c2.get_in_port("in").unwrap().write(Arc::new(ones.clone()));
+
+    let mut sums = Vec::new();
+
+    for _ in 0..3 {
+        c0.input();
+        c1.input();
+        c2.input();
+        c0.fire();
+        c1.fire();
+        c2.fire();
+        c0.output();
+        c1.output();
+        c2.output();
+
+        let (r0, _) = af::sum_all(&c0.get_out_port("out").unwrap().read());
+        let (r1, _) = af::sum_all(&c1.get_out_port("out").unwrap().read());
+        let (r2, _) = af::sum_all(&c2.get_out_port("out").unwrap().read());
+        sums.push((r0, r1, r2));
+    }
+
+    assert_eq!((sums[0].0, sums[0].1), (0.0, 15.0));
+    assert_eq!((sums[1].0, sums[1].1), (15.0, 30.0));
+    assert!((sums[0].2 - 15.0).abs() < 1e-9);
+    assert!((sums[1].2 - 22.5).abs() < 1e-9);
+    assert!((sums[2].2 - 26.25).abs() < 1e-9);
+
+    c1.get_in_port("reset").unwrap().write(Arc::new(ones));
+    c1.input();
+    c1.fire();
+    c1.output();
+
+    let (r1, _) = af::sum_all(&c1.get_out_port("out").unwrap().read());
+
+    assert_eq!(r1, 15.0);
+}
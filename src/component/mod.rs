@@ -1,12 +1,34 @@
 use port::Port;
 use unit::*;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use af;
 
 pub mod constant;
 pub mod pipe;
 pub mod null;
+pub mod noise;
+pub mod waveform;
+pub mod arith;
+pub mod activation;
+pub mod dense;
+pub mod conv;
+pub mod lif;
+pub mod izhikevich;
+pub mod adex;
+pub mod projection;
+pub mod hebbian;
+pub mod reservoir;
+pub mod memory;
+pub mod routing;
+pub mod shape;
+pub mod reduce;
+pub mod competition;
+pub mod pid;
+pub mod kalman;
+pub mod tabular;
+pub mod actor_critic;
+pub mod gridworld;
 
 pub trait Component : Unit {
     fn input(&mut self);
@@ -16,19 +38,36 @@ pub trait Component : Unit {
     fn fire(&mut self);
 }
 
+/// Reads a 1 × 1 port value, such as a control, index or reward signal.
+pub fn scalar(x: &af::Array) -> f64 {
+    let (value, _) = af::sum_all(x);
+    value
+}
+
+/// Reads a 1 × 1 port value as an index below `n`, or `None` when it is
+/// negative, too large or NaN.
+pub fn select(x: &af::Array, n: usize) -> Option<usize> {
+    let index = scalar(x).round();
+    if index >= 0.0 && index < n as f64 {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
 #[derive(Clone)]
 pub struct ComponentStruct {
     unit: UnitStruct,
-    inputs: HashMap<String, Arc<af::Array>>,
-    outputs: HashMap<String, Arc<af::Array>>,
+    inputs: BTreeMap<String, Arc<af::Array>>,
+    outputs: BTreeMap<String, Arc<af::Array>>,
 }
 
 impl ComponentStruct {
     pub fn new() -> Self {
         ComponentStruct {
             unit: UnitStruct::new(),
-            inputs: HashMap::new(),
-            outputs: HashMap::new(),
+            inputs: BTreeMap::new(),
+            outputs: BTreeMap::new(),
         }
     }
 }
@@ -88,10 +127,10 @@ impl Unit for ComponentStruct {
     delegate! {
         for unit;
         fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
-        fn get_in_ports(&mut self) -> &mut HashMap<String, Port>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
         fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
         fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
-        fn get_out_ports(&mut self) -> &mut HashMap<String, Port>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
         fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
         fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
     }
@@ -0,0 +1,249 @@
+use port::Port;
+use unit::*;
+use component::*;
+use random::Stream;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+#[derive(Clone)]
+pub struct UniformNoise {
+    base: ComponentStruct,
+    dims: af::Dim4,
+    stream: Stream,
+}
+
+impl UniformNoise {
+    pub fn new(dims: af::Dim4, stream: Stream) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_out_port("out", dims);
+        UniformNoise {
+            base: base,
+            dims: dims,
+            stream: stream,
+        }
+    }
+}
+
+impl Unit for UniformNoise {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for UniformNoise {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let value = self.stream.randu(self.dims);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[derive(Clone)]
+pub struct GaussianNoise {
+    base: ComponentStruct,
+    dims: af::Dim4,
+    mean: f64,
+    std: f64,
+    stream: Stream,
+}
+
+impl GaussianNoise {
+    pub fn new(dims: af::Dim4, mean: f64, std: f64, stream: Stream) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_out_port("out", dims);
+        GaussianNoise {
+            base: base,
+            dims: dims,
+            mean: mean,
+            std: std,
+            stream: stream,
+        }
+    }
+}
+
+impl Unit for GaussianNoise {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for GaussianNoise {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let noise = self.stream.randn(self.dims);
+        let scaled = af::mul(&noise, &af::constant(self.std, self.dims), false);
+        let value = af::add(&scaled, &af::constant(self.mean, self.dims), false);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[derive(Clone)]
+pub struct PoissonSpikes {
+    base: ComponentStruct,
+    dims: af::Dim4,
+    prob: af::Array,
+    stream: Stream,
+}
+
+impl PoissonSpikes {
+    pub fn new(rate: af::Array, dt: f64, stream: Stream) -> Self {
+        let dims = rate.dims();
+        let prob = af::mul(&rate, &af::constant(dt, dims), false);
+        let mut base = ComponentStruct::new();
+        base.make_out_port("out", dims);
+        PoissonSpikes {
+            base: base,
+            dims: dims,
+            prob: prob,
+            stream: stream,
+        }
+    }
+}
+
+impl Unit for PoissonSpikes {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for PoissonSpikes {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let draw = self.stream.randu(self.dims);
+        let value = af::lt(&draw, &self.prob, false).cast::<f64>();
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn noise_works() {
+    use random::Random;
+
+    af::set_backend(af::Backend::CPU);
+
+    let n_rows: u64 = 5;
+    let n_cols: u64 = 3;
+
+    let dims = af::Dim4::new(&[n_rows, n_cols, 1, 1]);
+    let random = Random::new(0);
+
+    let mut c0 = UniformNoise::new(dims, random.stream("c0"));
+    let mut c1 = GaussianNoise::new(dims, 10.0, 0.0, random.stream("c1"));
+    let mut c2 = PoissonSpikes::new(af::constant(1.0, dims), 1.0, random.stream("c2"));
+
+    c0.input();
+    c1.input();
+    c2.input();
+    c0.fire();
+    c1.fire();
+    c2.fire();
+    c0.output();
+    c1.output();
+    c2.output();
+
+    let a0 = c0.get_out_port("out").unwrap().read();
+    let a1 = c1.get_out_port("out").unwrap().read();
+    let a2 = c2.get_out_port("out").unwrap().read();
+    let (r0, _) = af::min_all(&a0);
+    let (r1, _) = af::max_all(&a0);
+    let (r2, _) = af::sum_all(&a1);
+    let (r3, _) = af::sum_all(&a2);
+
+    assert!(r0 >= 0.0);
+    assert!(r1 < 1.0);
+    assert_eq!(r2, 150.0);
+    assert_eq!(r3, 15.0);
+
+    let dims = af::Dim4::new(&[100, 100, 1, 1]);
+    let n = 10000.0;
+
+    let mut c3 = GaussianNoise::new(dims, 10.0, 2.0, random.stream("c3"));
+    let mut c4 = GaussianNoise::new(dims, 10.0, 2.0, random.stream("c3"));
+    let mut c5 = PoissonSpikes::new(af::constant(0.3, dims), 1.0, random.stream("c5"));
+
+    c3.input();
+    c4.input();
+    c5.input();
+    c3.fire();
+    c4.fire();
+    c5.fire();
+    c3.output();
+    c4.output();
+    c5.output();
+
+    let a3 = c3.get_out_port("out").unwrap().read();
+    let a4 = c4.get_out_port("out").unwrap().read();
+    let a5 = c5.get_out_port("out").unwrap().read();
+    let (sum, _) = af::sum_all(&a3);
+    let mean = sum / n;
+    let centered = &a3 - &af::constant(mean, dims);
+    let (squares, _) = af::sum_all(&(&centered * &centered));
+    let (r4, _) = af::sum_all(&af::abs(&(&a3 - &a4)));
+    let (r5, _) = af::sum_all(&a5);
+
+    assert!((mean - 10.0).abs() < 0.1);
+    assert!(((squares / n).sqrt() - 2.0).abs() < 0.1);
+    assert_eq!(r4, 0.0);
+    assert!((r5 / n - 0.3).abs() < 0.02);
+}
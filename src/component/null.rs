@@ -1,7 +1,7 @@
 use port::Port;
 use unit::*;
 use component::*;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use af;
 
@@ -23,12 +23,12 @@ impl Unit for Null {
         for base;
         fn make_in_port(&mut self, key: &str, dims: af::Dim4);
         fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
-        fn get_in_ports(&mut self) -> &mut HashMap<String, Port>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
         fn remove_in_port(&mut self, key: &str);
         fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
         fn make_out_port(&mut self, key: &str, dims: af::Dim4);
         fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
-        fn get_out_ports(&mut self) -> &mut HashMap<String, Port>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
         fn remove_out_port(&mut self, key: &str);
         fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
         fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
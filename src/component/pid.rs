@@ -0,0 +1,195 @@
+use port::Port;
+use unit::*;
+use component::*;
+use clock::Clock;
+use std::collections::BTreeMap;
+use std::f64::INFINITY;
+use std::sync::Arc;
+use af;
+
+/// Elementwise PID controller. Each step computes the error between
+/// "setpoint" and "measurement" and writes the clamped control signal to
+/// "out". The integral term is clamped to the same limits as the output to
+/// prevent windup, and the derivative of the error is low-pass filtered
+/// with time constant `filter`. The derivative is zero on the first step.
+/// The time step is read from `clock`, normally the network's clock.
+#[derive(Clone)]
+pub struct PID {
+    base: ComponentStruct,
+    kp: f64,
+    ki: f64,
+    kd: f64,
+    clock: Clock,
+    min: f64,
+    max: f64,
+    filter: f64,
+    integral: af::Array,
+    derivative: af::Array,
+    error: Option<af::Array>,
+}
+
+impl PID {
+    pub fn new(dims: af::Dim4, kp: f64, ki: f64, kd: f64, clock: Clock) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("setpoint", dims);
+        base.make_in_port("measurement", dims);
+        base.make_out_port("out", dims);
+        PID {
+            base: base,
+            kp: kp,
+            ki: ki,
+            kd: kd,
+            clock: clock,
+            min: -INFINITY,
+            max: INFINITY,
+            filter: 0.0,
+            integral: af::constant(0.0, dims),
+            derivative: af::constant(0.0, dims),
+            error: None,
+        }
+    }
+
+    pub fn with_limits(mut self, min: f64, max: f64) -> Self {
+        if min > max {
+            panic!("Invalid output limits (min: {} max: {})", min, max);
+        }
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: f64) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn reset(&mut self) {
+        let dims = self.integral.dims();
+        self.integral = af::constant(0.0, dims);
+        self.derivative = af::constant(0.0, dims);
+        self.error = None;
+    }
+
+    fn clamp(&self, x: &af::Array) -> af::Array {
+        let dims = x.dims();
+        let upper = af::minof(x, &af::constant(self.max, dims), false);
+        af::maxof(&upper, &af::constant(self.min, dims), false)
+    }
+
+    fn step(&mut self, setpoint: &af::Array, measurement: &af::Array) -> af::Array {
+        let dims = self.integral.dims();
+        let k = |x: f64| af::constant(x, dims);
+        let dt = self.clock.get_dt();
+        let error = setpoint - measurement;
+
+        if self.ki != 0.0 {
+            let integral = &self.integral + &(&error * &k(dt));
+            let term = self.clamp(&(&integral * &k(self.ki)));
+            self.integral = &term / &k(self.ki);
+        }
+
+        if let Some(ref previous) = self.error {
+            let raw = &(&error - previous) / &k(dt);
+            let alpha = dt / (self.filter + dt);
+            self.derivative = &self.derivative + &(&(&raw - &self.derivative) * &k(alpha));
+        }
+        self.error = Some(error.clone());
+
+        let p = &error * &k(self.kp);
+        let i = &self.integral * &k(self.ki);
+        let d = &self.derivative * &k(self.kd);
+        self.clamp(&(&(&p + &i) + &d))
+    }
+}
+
+impl Unit for PID {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for PID {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let setpoint = self.base.get_input("setpoint");
+        let measurement = self.base.get_input("measurement");
+        let value = self.step(&*setpoint, &*measurement);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn pid_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let dims = af::Dim4::new(&[2, 1, 1, 1]);
+    let clock = Clock::new(1.0);
+
+    let mut c0 = PID::new(dims, 1.0, 1.0, 1.0, clock.clone());
+    let mut c1 = PID::new(dims, 0.0, 1.0, 0.0, clock.clone()).with_limits(-2.0, 2.0);
+
+    c0.get_in_port("setpoint").unwrap().write(Arc::new(af::Array::new(&[1.0, -1.0], dims)));
+    c1.get_in_port("setpoint").unwrap().write(Arc::new(af::constant(1.0, dims)));
+
+    let mut sums = Vec::new();
+
+    for _ in 0..5 {
+        c0.input();
+        c1.input();
+        c0.fire();
+        c1.fire();
+        c0.output();
+        c1.output();
+        clock.tick();
+
+        let mut r0 = [0.0; 2];
+        c0.get_out_port("out").unwrap().read().host(&mut r0);
+        let (r1, _) = af::sum_all(&c1.get_out_port("out").unwrap().read());
+        sums.push((r0, r1));
+    }
+
+    assert_eq!(sums[0].0, [2.0, -2.0]);
+    assert_eq!(sums[1].0, [3.0, -3.0]);
+    assert_eq!(sums[1].1, 4.0);
+    assert_eq!(sums[4].1, 4.0);
+
+    c1.get_in_port("setpoint").unwrap().write(Arc::new(af::constant(-1.0, dims)));
+    c1.input();
+    c1.fire();
+    c1.output();
+
+    let (r1, _) = af::sum_all(&c1.get_out_port("out").unwrap().read());
+
+    assert_eq!(r1, 2.0);
+
+    let mut c2 = PID::new(dims, 0.0, 1.0, 0.0, Clock::new(0.5));
+
+    c2.get_in_port("setpoint").unwrap().write(Arc::new(af::constant(1.0, dims)));
+    c2.input();
+    c2.fire();
+    c2.output();
+
+    let (r2, _) = af::sum_all(&c2.get_out_port("out").unwrap().read());
+
+    assert_eq!(r2, 1.0);
+}
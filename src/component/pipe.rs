@@ -1,21 +1,56 @@
 use port::Port;
 use unit::*;
 use component::*;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use af;
 
+/// Forwards inputs to outputs under a list of `(from, to)` mappings. An
+/// input may appear in several mappings to fan out to multiple outputs.
+/// `with_map` creates the ports for its mappings, each with its own shape;
+/// `new` leaves that to the caller, and `input` panics if a mapped port has
+/// not been made by then.
 #[derive(Clone)]
 pub struct Pipe {
     base: ComponentStruct,
-    map: (String, String),
+    map: Vec<(String, String)>,
 }
 
 impl Pipe {
     pub fn new(map: (&str, &str)) -> Self {
         Pipe {
             base: ComponentStruct::new(),
-            map: (map.0.to_string(), map.1.to_string()),
+            map: vec![(map.0.to_string(), map.1.to_string())],
+        }
+    }
+
+    pub fn with_map(map: &[(&str, &str, af::Dim4)]) -> Self {
+        if map.is_empty() {
+            panic!("Pipe needs at least one mapping.");
+        }
+        let mut base = ComponentStruct::new();
+        let mut sources = BTreeMap::<&str, af::Dim4>::new();
+        let mut targets = Vec::<&str>::new();
+        for &(from, to, dims) in map {
+            if from.is_empty() || to.is_empty() {
+                panic!("Mapping ({:?}, {:?}) has an empty port name.", from, to);
+            }
+            if targets.contains(&to) {
+                panic!("Output {} is mapped more than once.", to);
+            }
+            if let Some(&other) = sources.get(from) {
+                if other != dims {
+                    panic!("Mismatched dimensions for input {} (expected: {} actual: {})", from, other, dims);
+                }
+            }
+            targets.push(to);
+            sources.insert(from, dims);
+            base.make_in_port(from, dims);
+            base.make_out_port(to, dims);
+        }
+        Pipe {
+            base: base,
+            map: map.iter().map(|&(from, to, _)| (from.to_string(), to.to_string())).collect(),
         }
     }
 }
@@ -25,12 +60,12 @@ impl Unit for Pipe {
         for base;
         fn make_in_port(&mut self, key: &str, dims: af::Dim4);
         fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
-        fn get_in_ports(&mut self) -> &mut HashMap<String, Port>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
         fn remove_in_port(&mut self, key: &str);
         fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
         fn make_out_port(&mut self, key: &str, dims: af::Dim4);
         fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
-        fn get_out_ports(&mut self) -> &mut HashMap<String, Port>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
         fn remove_out_port(&mut self, key: &str);
         fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
         fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
@@ -40,21 +75,29 @@ impl Unit for Pipe {
 impl Component for Pipe {
     delegate! {
         for base;
-        fn input(&mut self);
         fn output(&mut self);
         fn get_input(&mut self, key: &str) -> Arc<af::Array>;
         fn get_output(&mut self, key: &str) -> Arc<af::Array>;
     }
 
+    fn input(&mut self) {
+        for &(ref from, ref to) in &self.map {
+            if self.base.get_in_port(from).is_err() {
+                panic!("Input {} of mapping ({}, {}) does not exist.", from, from, to);
+            }
+            if self.base.get_out_port(to).is_err() {
+                panic!("Output {} of mapping ({}, {}) does not exist.", to, from, to);
+            }
+        }
+        self.base.input();
+    }
+
     fn fire(&mut self) {
-        let ref inputs = self.base.inputs;
-        let (from, to) = self.map.clone();
-        let mut outputs = HashMap::<String, Arc<af::Array>>::new();
-        match inputs.get(&from) {
-            Some(x) => outputs.insert(to, x.clone()),
-            None    => panic!("Input {} does not exist.", from),
-        };
-        self.base.outputs = outputs;
+        for &(ref from, ref to) in &self.map {
+            if let Some(x) = self.base.inputs.get(from) {
+                self.base.outputs.insert(to.clone(), x.clone());
+            }
+        }
     }
 }
 
@@ -88,3 +131,35 @@ fn pipe_works() {
 
     assert_eq!(r0, 15.0);
 }
+
+#[test]
+fn pipe_with_map_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let n_rows: u64 = 5;
+    let n_cols: u64 = 3;
+
+    let dims = af::Dim4::new(&[n_rows, n_cols, 1, 1]);
+    let ones = af::constant(1.0, dims);
+    let wide = af::Dim4::new(&[n_rows, 2 * n_cols, 1, 1]);
+    let twos = af::constant(2.0, wide);
+
+    let mut c0 = Pipe::with_map(&[("a", "x", dims), ("a", "y", dims), ("b", "z", wide)]);
+
+    assert_eq!(c0.get_in_ports().len(), 2);
+    assert_eq!(c0.get_out_ports().len(), 3);
+
+    c0.get_in_port("a").unwrap().write(Arc::new(ones));
+    c0.get_in_port("b").unwrap().write(Arc::new(twos));
+    c0.input();
+    c0.fire();
+    c0.output();
+
+    let (r0, _) = af::sum_all(&c0.get_out_port("x").unwrap().read());
+    let (r1, _) = af::sum_all(&c0.get_out_port("y").unwrap().read());
+    let (r2, _) = af::sum_all(&c0.get_out_port("z").unwrap().read());
+
+    assert_eq!(r0, 15.0);
+    assert_eq!(r1, 15.0);
+    assert_eq!(r2, 60.0);
+}
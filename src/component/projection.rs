@@ -0,0 +1,304 @@
+use port::Port;
+use unit::*;
+use component::*;
+use component::dense::{Init, initialize};
+use random::Stream;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Connectivity {
+    AllToAll,
+    OneToOne,
+    FixedProbability(f64),
+    /// Lays both populations out on 2D grids of the given (rows, cols),
+    /// scales the postsynaptic grid onto the presynaptic one and connects
+    /// every pair whose distance is at most `radius` presynaptic cells.
+    Distance { pre: (u64, u64), post: (u64, u64), radius: f64 },
+}
+
+fn grid_mask<F>(n_pre: u64, n_post: u64, connected: F) -> af::Array
+    where F: Fn(u64, u64) -> bool
+{
+    let mut values = Vec::with_capacity((n_pre * n_post) as usize);
+    for j in 0..n_pre {
+        for i in 0..n_post {
+            values.push(if connected(j, i) { 1.0 } else { 0.0 });
+        }
+    }
+    af::Array::new(&values, af::Dim4::new(&[n_post, n_pre, 1, 1]))
+}
+
+/// Builds an `n_post` × `n_pre` matrix of ones and zeros marking which
+/// synapses exist under `connectivity`.
+pub fn connect_mask(connectivity: Connectivity, n_pre: u64, n_post: u64, stream: &mut Stream) -> af::Array {
+    let dims = af::Dim4::new(&[n_post, n_pre, 1, 1]);
+    match connectivity {
+        Connectivity::AllToAll => af::constant(1.0, dims),
+        Connectivity::OneToOne => {
+            if n_pre != n_post {
+                panic!("One-to-one connectivity requires equal sizes (pre: {} post: {})", n_pre, n_post);
+            }
+            grid_mask(n_pre, n_post, |j, i| i == j)
+        },
+        Connectivity::FixedProbability(p) => {
+            af::lt(&stream.randu(dims), &af::constant(p, dims), false).cast::<f64>()
+        },
+        Connectivity::Distance { pre, post, radius } => {
+            if pre.0 * pre.1 != n_pre || post.0 * post.1 != n_post {
+                panic!("Grid shapes do not match population sizes (pre: {} post: {})", n_pre, n_post);
+            }
+            let sy = pre.0 as f64 / post.0 as f64;
+            let sx = pre.1 as f64 / post.1 as f64;
+            grid_mask(n_pre, n_post, |j, i| {
+                let (py, px) = ((j % pre.0) as f64, (j / pre.0) as f64);
+                let (qy, qx) = ((i % post.0) as f64 * sy, (i / post.0) as f64 * sx);
+                let (dy, dx) = (py - qy, px - qx);
+                (dy * dy + dx * dx).sqrt() <= radius
+            })
+        },
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct STDP {
+    pub a_plus: f64,
+    pub a_minus: f64,
+    pub tau_plus: f64,
+    pub tau_minus: f64,
+    pub w_min: f64,
+    pub w_max: f64,
+}
+
+impl Default for STDP {
+    fn default() -> Self {
+        STDP {
+            a_plus: 0.01,
+            a_minus: 0.012,
+            tau_plus: 20.0,
+            tau_minus: 20.0,
+            w_min: 0.0,
+            w_max: 1.0,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Plasticity {
+    params: STDP,
+    dt: f64,
+    pre_trace: af::Array,
+    post_trace: af::Array,
+}
+
+/// Converts presynaptic activity on "in" (`n_pre` × 1) into postsynaptic
+/// currents on "out" (`n_post` × 1) through a masked weight matrix. Absent
+/// synapses are held at zero weight.
+#[derive(Clone)]
+pub struct Projection {
+    base: ComponentStruct,
+    weights: af::Array,
+    mask: af::Array,
+    plasticity: Option<Plasticity>,
+}
+
+impl Projection {
+    pub fn new(n_pre: u64, n_post: u64, connectivity: Connectivity, init: Init, stream: &mut Stream) -> Self {
+        let dims = af::Dim4::new(&[n_post, n_pre, 1, 1]);
+        let mask = connect_mask(connectivity, n_pre, n_post, stream);
+        let weights = af::mul(&initialize(init, dims, stream), &mask, false);
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", af::Dim4::new(&[n_pre, 1, 1, 1]));
+        base.make_out_port("out", af::Dim4::new(&[n_post, 1, 1, 1]));
+        Projection {
+            base: base,
+            weights: weights,
+            mask: mask,
+            plasticity: None,
+        }
+    }
+
+    /// Enables spike-timing-dependent plasticity. Postsynaptic spikes are
+    /// read from the additional "post" port, and after every step each
+    /// existing synapse is potentiated by `a_plus` times the presynaptic
+    /// trace on a postsynaptic spike, depressed by `a_minus` times the
+    /// postsynaptic trace on a presynaptic spike, and clamped to
+    /// [`w_min`, `w_max`].
+    pub fn with_stdp(mut self, params: STDP, dt: f64) -> Self {
+        let n_post = self.weights.dims()[0];
+        let n_pre = self.weights.dims()[1];
+        self.base.make_in_port("post", af::Dim4::new(&[n_post, 1, 1, 1]));
+        self.plasticity = Some(Plasticity {
+            params: params,
+            dt: dt,
+            pre_trace: af::constant(0.0, af::Dim4::new(&[n_pre, 1, 1, 1])),
+            post_trace: af::constant(0.0, af::Dim4::new(&[n_post, 1, 1, 1])),
+        });
+        self
+    }
+
+    pub fn get_traces(&self) -> Option<(&af::Array, &af::Array)> {
+        self.plasticity.as_ref().map(|p| (&p.pre_trace, &p.post_trace))
+    }
+
+    fn learn(&mut self, pre: &af::Array, post: &af::Array) {
+        let plasticity = match self.plasticity {
+            Some(ref mut x) => x,
+            None    => return,
+        };
+        let p = plasticity.params;
+        let k = |x: f64, dims: af::Dim4| af::constant(x, dims);
+
+        let pre_dims = pre.dims();
+        let post_dims = post.dims();
+        let pre_decay = k((-plasticity.dt / p.tau_plus).exp(), pre_dims);
+        let post_decay = k((-plasticity.dt / p.tau_minus).exp(), post_dims);
+        plasticity.pre_trace = &(&plasticity.pre_trace * &pre_decay) + pre;
+        plasticity.post_trace = &(&plasticity.post_trace * &post_decay) + post;
+
+        let ltp = af::matmul(post, &plasticity.pre_trace, af::MatProp::NONE, af::MatProp::TRANS);
+        let ltd = af::matmul(&plasticity.post_trace, pre, af::MatProp::NONE, af::MatProp::TRANS);
+
+        let dims = self.weights.dims();
+        let dw = &(&ltp * &k(p.a_plus, dims)) - &(&ltd * &k(p.a_minus, dims));
+        let updated = &self.weights + &dw;
+        let clamped = af::maxof(&af::minof(&updated, &k(p.w_max, dims), false), &k(p.w_min, dims), false);
+        self.weights = af::mul(&clamped, &self.mask, false);
+    }
+
+    pub fn get_weights(&self) -> &af::Array {
+        &self.weights
+    }
+
+    pub fn set_weights(&mut self, weights: af::Array) {
+        if self.weights.dims() != weights.dims() {
+            panic!("Mismatched weight dimensions (expected: {} actual: {})", self.weights.dims(), weights.dims());
+        }
+        self.weights = af::mul(&weights, &self.mask, false);
+    }
+
+    pub fn get_mask(&self) -> &af::Array {
+        &self.mask
+    }
+
+    pub fn count_synapses(&self) -> u64 {
+        let (count, _) = af::sum_all(&self.mask);
+        count as u64
+    }
+}
+
+impl Unit for Projection {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Projection {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let spikes = self.base.get_input("in");
+        let value = af::matmul(&self.weights, &*spikes, af::MatProp::NONE, af::MatProp::NONE);
+        if self.plasticity.is_some() {
+            let post = self.base.get_input("post");
+            self.learn(&*spikes, &*post);
+        }
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn projection_works() {
+    use random::Random;
+
+    af::set_backend(af::Backend::CPU);
+
+    let mut stream = Random::new(0).stream("projection");
+
+    let c0 = Projection::new(4, 3, Connectivity::AllToAll, Init::Uniform(1.0, 1.0), &mut stream);
+    let c1 = Projection::new(4, 4, Connectivity::OneToOne, Init::Uniform(1.0, 1.0), &mut stream);
+    let c2 = Projection::new(100, 100, Connectivity::FixedProbability(0.0), Init::Xavier, &mut stream);
+    let distance = Connectivity::Distance { pre: (3, 3), post: (3, 3), radius: 1.0 };
+    let mut c3 = Projection::new(9, 9, distance, Init::Uniform(1.0, 1.0), &mut stream);
+
+    assert_eq!(c0.count_synapses(), 12);
+    assert_eq!(c1.count_synapses(), 4);
+    assert_eq!(c2.count_synapses(), 0);
+    assert_eq!(c3.count_synapses(), 9 + 2 * 12);
+
+    c3.get_in_port("in").unwrap().write(Arc::new(af::constant(1.0, af::Dim4::new(&[9, 1, 1, 1]))));
+    c3.input();
+    c3.fire();
+    c3.output();
+
+    let a0 = c3.get_out_port("out").unwrap().read();
+    let (r0, _) = af::sum_all(&a0);
+
+    assert_eq!(r0, 33.0);
+}
+
+#[test]
+fn stdp_works() {
+    use random::Random;
+
+    af::set_backend(af::Backend::CPU);
+
+    let mut stream = Random::new(0).stream("stdp");
+    let pre = af::Dim4::new(&[2, 1, 1, 1]);
+    let post = af::Dim4::new(&[1, 1, 1, 1]);
+
+    let params = STDP {
+        a_plus: 0.1,
+        a_minus: 0.1,
+        tau_plus: 10.0,
+        tau_minus: 10.0,
+        w_min: 0.0,
+        w_max: 1.0,
+    };
+
+    let mut c0 = Projection::new(2, 1, Connectivity::AllToAll, Init::Uniform(0.5, 0.5), &mut stream)
+        .with_stdp(params, 1.0);
+
+    c0.get_in_port("in").unwrap().write(Arc::new(af::Array::new(&[1.0, 0.0], pre)));
+    c0.input();
+    c0.fire();
+    c0.output();
+
+    c0.get_in_port("in").unwrap().write(Arc::new(af::constant(0.0, pre)));
+    c0.get_in_port("post").unwrap().write(Arc::new(af::constant(1.0, post)));
+    c0.input();
+    c0.fire();
+    c0.output();
+
+    c0.get_in_port("in").unwrap().write(Arc::new(af::Array::new(&[0.0, 1.0], pre)));
+    c0.get_in_port("post").unwrap().write(Arc::new(af::constant(0.0, post)));
+    c0.input();
+    c0.fire();
+    c0.output();
+
+    let mut weights = [0.0; 2];
+    c0.get_weights().host(&mut weights);
+
+    assert!(weights[0] > 0.5);
+    assert!(weights[1] < 0.5);
+}
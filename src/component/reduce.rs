@@ -0,0 +1,244 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reduction {
+    Sum,
+    Mean,
+    Max,
+    Min,
+    ArgMax,
+}
+
+pub fn reduce(reduction: Reduction, x: &af::Array, dim: Option<usize>) -> af::Array {
+    match dim {
+        Some(d) => match reduction {
+            Reduction::Sum => af::sum(x, d as i32),
+            Reduction::Mean => af::mean(x, d as i64),
+            Reduction::Max => af::max(x, d as i32),
+            Reduction::Min => af::min(x, d as i32),
+            Reduction::ArgMax => {
+                let (_, index) = af::imax(x, d as i32);
+                index.cast::<f64>()
+            },
+        },
+        None => {
+            let value = match reduction {
+                Reduction::Sum => af::sum_all(x).0,
+                Reduction::Mean => af::mean_all(x).0,
+                Reduction::Max => af::max_all(x).0,
+                Reduction::Min => af::min_all(x).0,
+                Reduction::ArgMax => af::imax_all(x).2 as f64,
+            };
+            af::constant(value, af::Dim4::new(&[1, 1, 1, 1]))
+        },
+    }
+}
+
+/// Reduces "in" along `dim`, or over all elements into a 1 × 1 array when
+/// `dim` is `None`, and writes the result to "out".
+#[derive(Clone)]
+pub struct Reduce {
+    base: ComponentStruct,
+    reduction: Reduction,
+    dim: Option<usize>,
+}
+
+impl Reduce {
+    pub fn new(reduction: Reduction, dims: af::Dim4, dim: Option<usize>) -> Self {
+        let mut out = [1, 1, 1, 1];
+        if let Some(d) = dim {
+            if d >= 4 {
+                panic!("Dimension {} out of range (expected: 0..4)", d);
+            }
+            out = [dims[0], dims[1], dims[2], dims[3]];
+            out[d] = 1;
+        }
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", af::Dim4::new(&out));
+        Reduce {
+            base: base,
+            reduction: reduction,
+            dim: dim,
+        }
+    }
+}
+
+impl Unit for Reduce {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Reduce {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let value = reduce(self.reduction, &*x, self.dim);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+/// Tracks the exponential moving mean and variance of "in" over time with
+/// smoothing factor `alpha`, writing them to "mean" and "var". The first
+/// sample initializes the mean with zero variance.
+#[derive(Clone)]
+pub struct RunningStats {
+    base: ComponentStruct,
+    alpha: f64,
+    mean: af::Array,
+    var: af::Array,
+    initialized: bool,
+}
+
+impl RunningStats {
+    pub fn new(dims: af::Dim4, alpha: f64) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("mean", dims);
+        base.make_out_port("var", dims);
+        RunningStats {
+            base: base,
+            alpha: alpha,
+            mean: af::constant(0.0, dims),
+            var: af::constant(0.0, dims),
+            initialized: false,
+        }
+    }
+
+    fn update(&mut self, x: &af::Array) {
+        let dims = self.mean.dims();
+        if !self.initialized {
+            self.mean = x.clone();
+            self.var = af::constant(0.0, dims);
+            self.initialized = true;
+            return;
+        }
+        let k = |x: f64| af::constant(x, dims);
+        let delta = x - &self.mean;
+        self.mean = &self.mean + &(&delta * &k(self.alpha));
+        let spread = &self.var + &(&(&delta * &delta) * &k(self.alpha));
+        self.var = &spread * &k(1.0 - self.alpha);
+    }
+}
+
+impl Unit for RunningStats {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for RunningStats {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        self.update(&*x);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("mean".to_string(), Arc::new(self.mean.clone()));
+        outputs.insert("var".to_string(), Arc::new(self.var.clone()));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn reduce_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let n_rows: u64 = 5;
+    let n_cols: u64 = 3;
+
+    let dims = af::Dim4::new(&[n_rows, n_cols, 1, 1]);
+    let values: Vec<f64> = (0..15).map(|x| x as f64).collect();
+    let x = af::Array::new(&values, dims);
+
+    let (r0, _) = af::sum_all(&reduce(Reduction::Sum, &x, None));
+    let (r1, _) = af::sum_all(&reduce(Reduction::Mean, &x, None));
+    let (r2, _) = af::sum_all(&reduce(Reduction::ArgMax, &x, None));
+    let (r3, _) = af::sum_all(&reduce(Reduction::Max, &x, Some(0)));
+    let (r4, _) = af::sum_all(&reduce(Reduction::ArgMax, &x, Some(1)));
+
+    assert_eq!(r0, 105.0);
+    assert_eq!(r1, 7.0);
+    assert_eq!(r2, 14.0);
+    assert_eq!(r3, 4.0 + 9.0 + 14.0);
+    assert_eq!(r4, 10.0);
+
+    let mut c0 = Reduce::new(Reduction::Min, dims, Some(1));
+    let mut c1 = RunningStats::new(dims, 0.5);
+
+    c0.get_in_port("in").unwrap().write(Arc::new(x));
+    c1.get_in_port("in").unwrap().write(Arc::new(af::constant(2.0, dims)));
+
+    for _ in 0..2 {
+        c0.input();
+        c1.input();
+        c0.fire();
+        c1.fire();
+        c0.output();
+        c1.output();
+    }
+
+    let a0 = c0.get_out_port("out").unwrap().read();
+    let (r0, _) = af::sum_all(&a0);
+    let (r1, _) = af::sum_all(&c1.get_out_port("mean").unwrap().read());
+    let (r2, _) = af::sum_all(&c1.get_out_port("var").unwrap().read());
+
+    assert_eq!(a0.dims(), af::Dim4::new(&[n_rows, 1, 1, 1]));
+    assert_eq!(r0, 10.0);
+    assert_eq!(r1, 15.0 * 2.0);
+    assert_eq!(r2, 0.0);
+
+    c1.get_in_port("in").unwrap().write(Arc::new(af::constant(4.0, dims)));
+    c1.input();
+    c1.fire();
+    c1.output();
+
+    let (r1, _) = af::sum_all(&c1.get_out_port("mean").unwrap().read());
+    let (r2, _) = af::sum_all(&c1.get_out_port("var").unwrap().read());
+
+    assert_eq!(r1, 15.0 * 3.0);
+    assert_eq!(r2, 15.0 * 1.0);
+}
@@ -0,0 +1,265 @@
+use port::Port;
+use unit::*;
+use component::*;
+use random::Stream;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+/// Estimates the spectral radius of a square matrix with Gelfand's formula,
+/// `ρ(W) = lim ‖Wᵏ‖^(1/k)`, by repeatedly applying `W` to a vector. The
+/// first half of the iterations only lets the vector settle and is left out
+/// of the average, so transients from the starting vector do not bias it.
+/// A complex dominant pair makes the per-step norms oscillate, with an error
+/// that shrinks only as one over the number of averaged steps, so random
+/// matrices need hundreds of iterations or more.
+pub fn spectral_radius(w: &af::Array, iterations: u32) -> f64 {
+    if iterations == 0 {
+        panic!("Spectral radius estimate needs at least one iteration");
+    }
+    let n = w.dims()[0];
+    let burn_in = iterations / 2;
+    let mut x = af::constant(1.0 / (n as f64).sqrt(), af::Dim4::new(&[n, 1, 1, 1]));
+    let mut log_norm = 0.0;
+    for i in 0..iterations {
+        x = af::matmul(w, &x, af::MatProp::NONE, af::MatProp::NONE);
+        let norm = af::norm(&x, af::NormType::VECTOR_2, 0.0, 0.0);
+        if norm == 0.0 {
+            return 0.0;
+        }
+        if i >= burn_in {
+            log_norm += norm.ln();
+        }
+        x = af::div(&x, &af::constant(norm, x.dims()), false);
+    }
+    (log_norm / (iterations - burn_in) as f64).exp()
+}
+
+/// Echo state network. Each step updates the state on "out" (`n_res` × 1)
+/// from the input on "in" (`n_in` × 1) as
+/// `x ← (1 - α) x + α tanh(W_in u + W x)`.
+#[derive(Clone)]
+pub struct Reservoir {
+    base: ComponentStruct,
+    leak: f64,
+    input_weights: af::Array,
+    weights: af::Array,
+    state: af::Array,
+}
+
+impl Reservoir {
+    pub fn new(n_in: u64, n_res: u64, radius: f64, leak: f64, scaling: f64, stream: &mut Stream) -> Self {
+        let k = |x: f64, dims: af::Dim4| af::constant(x, dims);
+
+        let in_dims = af::Dim4::new(&[n_res, n_in, 1, 1]);
+        let input_weights = &(&stream.randu(in_dims) * &k(2.0 * scaling, in_dims)) - &k(scaling, in_dims);
+
+        let res_dims = af::Dim4::new(&[n_res, n_res, 1, 1]);
+        let raw = &(&stream.randu(res_dims) * &k(2.0, res_dims)) - &k(1.0, res_dims);
+        let estimate = spectral_radius(&raw, 1000);
+        if estimate == 0.0 {
+            panic!("Random reservoir weights have zero spectral radius and cannot be scaled to {}", radius);
+        }
+        let scale = radius / estimate;
+        let weights = &raw * &k(scale, res_dims);
+
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", af::Dim4::new(&[n_in, 1, 1, 1]));
+        base.make_out_port("out", af::Dim4::new(&[n_res, 1, 1, 1]));
+        Reservoir {
+            base: base,
+            leak: leak,
+            input_weights: input_weights,
+            weights: weights,
+            state: af::constant(0.0, af::Dim4::new(&[n_res, 1, 1, 1])),
+        }
+    }
+
+    pub fn get_input_weights(&self) -> &af::Array {
+        &self.input_weights
+    }
+
+    pub fn get_weights(&self) -> &af::Array {
+        &self.weights
+    }
+
+    pub fn get_state(&self) -> &af::Array {
+        &self.state
+    }
+
+    pub fn reset(&mut self) {
+        self.state = af::constant(0.0, self.state.dims());
+    }
+
+    fn step(&mut self, u: &af::Array) -> af::Array {
+        let dims = self.state.dims();
+        let drive = af::matmul(&self.input_weights, u, af::MatProp::NONE, af::MatProp::NONE);
+        let recurrent = af::matmul(&self.weights, &self.state, af::MatProp::NONE, af::MatProp::NONE);
+        let activation = af::tanh(&(&drive + &recurrent));
+        let kept = &self.state * &af::constant(1.0 - self.leak, dims);
+        self.state = &kept + &(&activation * &af::constant(self.leak, dims));
+        self.state.clone()
+    }
+}
+
+impl Unit for Reservoir {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Reservoir {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let u = self.base.get_input("in");
+        let value = self.step(&*u);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+/// Linear readout of a reservoir state on "in" (`n_res` × 1) to "out"
+/// (`n_out` × 1). Weights are fitted offline with `train`.
+#[derive(Clone)]
+pub struct Readout {
+    base: ComponentStruct,
+    weights: af::Array,
+}
+
+impl Readout {
+    pub fn new(n_res: u64, n_out: u64) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", af::Dim4::new(&[n_res, 1, 1, 1]));
+        base.make_out_port("out", af::Dim4::new(&[n_out, 1, 1, 1]));
+        Readout {
+            base: base,
+            weights: af::constant(0.0, af::Dim4::new(&[n_out, n_res, 1, 1])),
+        }
+    }
+
+    pub fn get_weights(&self) -> &af::Array {
+        &self.weights
+    }
+
+    /// Fits the weights by ridge regression, `W = Y Xᵀ (X Xᵀ + λI)⁻¹`, from
+    /// recorded `states` (`n_res` × T) and `targets` (`n_out` × T).
+    pub fn train(&mut self, states: &af::Array, targets: &af::Array, ridge: f64) {
+        let n_res = self.weights.dims()[1];
+        if states.dims()[0] != n_res || states.dims()[1] != targets.dims()[1] {
+            panic!("Mismatched training data dimensions (states: {} targets: {})", states.dims(), targets.dims());
+        }
+        let gram = af::matmul(states, states, af::MatProp::NONE, af::MatProp::TRANS);
+        let regularizer = af::diag_create(&af::constant(ridge, af::Dim4::new(&[n_res, 1, 1, 1])), 0);
+        let cross = af::matmul(states, targets, af::MatProp::NONE, af::MatProp::TRANS);
+        let solution = af::solve(&(&gram + &regularizer), &cross, af::MatProp::NONE);
+        self.weights = af::transpose(&solution, false);
+    }
+}
+
+impl Unit for Readout {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Readout {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let value = af::matmul(&self.weights, &*x, af::MatProp::NONE, af::MatProp::NONE);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn reservoir_works() {
+    use random::Random;
+
+    af::set_backend(af::Backend::CPU);
+
+    let n_in: u64 = 1;
+    let n_res: u64 = 20;
+    let steps: u64 = 50;
+
+    let diagonal = af::diag_create(&af::Array::new(&[0.5, -0.9, 0.3], af::Dim4::new(&[3, 1, 1, 1])), 0);
+    let (sin, cos) = (1.0f64.sin(), 1.0f64.cos());
+    let rotation = af::Array::new(&[0.8 * cos, 0.8 * sin, -0.8 * sin, 0.8 * cos], af::Dim4::new(&[2, 2, 1, 1]));
+
+    assert!((spectral_radius(&diagonal, 100) - 0.9).abs() < 1e-6);
+    assert!((spectral_radius(&rotation, 100) - 0.8).abs() < 1e-9);
+
+    let mut stream = Random::new(0).stream("reservoir");
+    let mut c0 = Reservoir::new(n_in, n_res, 0.9, 0.5, 1.0, &mut stream);
+    let mut c1 = Readout::new(n_res, 1);
+
+    assert!((spectral_radius(c0.get_weights(), 4000) - 0.9).abs() < 0.02);
+
+    connect(&mut c1, "in", &mut c0, "out");
+
+    let mut states: Option<af::Array> = None;
+    let mut targets = Vec::new();
+
+    for t in 0..steps {
+        let u = (t as f64 * 0.3).sin();
+        c0.get_in_port("in").unwrap().write(Arc::new(af::constant(u, af::Dim4::new(&[1, 1, 1, 1]))));
+        c0.input();
+        c0.fire();
+        c0.output();
+        states = Some(match states {
+            Some(s) => af::join(1, &s, c0.get_state()),
+            None    => c0.get_state().clone(),
+        });
+        targets.push(u);
+    }
+
+    let targets = af::Array::new(&targets, af::Dim4::new(&[1, steps, 1, 1]));
+    c1.train(&states.unwrap(), &targets, 1e-6);
+
+    c1.input();
+    c1.fire();
+    c1.output();
+
+    let a0 = c1.get_out_port("out").unwrap().read();
+    let (r0, _) = af::sum_all(&a0);
+
+    assert!((r0 - (49.0 * 0.3 as f64).sin()).abs() < 0.1);
+}
@@ -0,0 +1,338 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+/// Passes "in" through to "out" while the 1 × 1 "control" port is nonzero
+/// and writes zeros otherwise.
+#[derive(Clone)]
+pub struct Gate {
+    base: ComponentStruct,
+    dims: af::Dim4,
+}
+
+impl Gate {
+    pub fn new(dims: af::Dim4) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_in_port("control", af::Dim4::new(&[1, 1, 1, 1]));
+        base.make_out_port("out", dims);
+        Gate {
+            base: base,
+            dims: dims,
+        }
+    }
+}
+
+impl Unit for Gate {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Gate {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let control = self.base.get_input("control");
+        let value = if scalar(&*control) != 0.0 {
+            self.base.get_input("in")
+        } else {
+            Arc::new(af::constant(0.0, self.dims))
+        };
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), value);
+        self.base.outputs = outputs;
+    }
+}
+
+/// Forwards one of the inputs "in0" through "in{n-1}" to "out", chosen by
+/// the index on the 1 × 1 "select" port. An index out of range selects
+/// nothing and writes zeros.
+#[derive(Clone)]
+pub struct Mux {
+    base: ComponentStruct,
+    dims: af::Dim4,
+    n: usize,
+}
+
+impl Mux {
+    pub fn new(dims: af::Dim4, n: usize) -> Self {
+        let mut base = ComponentStruct::new();
+        for i in 0..n {
+            base.make_in_port(&format!("in{}", i), dims);
+        }
+        base.make_in_port("select", af::Dim4::new(&[1, 1, 1, 1]));
+        base.make_out_port("out", dims);
+        Mux {
+            base: base,
+            dims: dims,
+            n: n,
+        }
+    }
+}
+
+impl Unit for Mux {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Mux {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let value = match select(&*self.base.get_input("select"), self.n) {
+            Some(index) => self.base.get_input(&format!("in{}", index)),
+            None        => Arc::new(af::constant(0.0, self.dims)),
+        };
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), value);
+        self.base.outputs = outputs;
+    }
+}
+
+/// Forwards "in" to one of the outputs "out0" through "out{n-1}", chosen by
+/// the index on the 1 × 1 "select" port. The other outputs are zeros, as
+/// are all of them when the index is out of range.
+#[derive(Clone)]
+pub struct Demux {
+    base: ComponentStruct,
+    dims: af::Dim4,
+    n: usize,
+}
+
+impl Demux {
+    pub fn new(dims: af::Dim4, n: usize) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_in_port("select", af::Dim4::new(&[1, 1, 1, 1]));
+        for i in 0..n {
+            base.make_out_port(&format!("out{}", i), dims);
+        }
+        Demux {
+            base: base,
+            dims: dims,
+            n: n,
+        }
+    }
+}
+
+impl Unit for Demux {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Demux {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let index = select(&*self.base.get_input("select"), self.n);
+        let value = self.base.get_input("in");
+        let zeros = Arc::new(af::constant(0.0, self.dims));
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        for i in 0..self.n {
+            let x = if Some(i) == index { value.clone() } else { zeros.clone() };
+            outputs.insert(format!("out{}", i), x);
+        }
+        self.base.outputs = outputs;
+    }
+}
+
+/// Writes the weighted sum of "in0" through "in{n-1}" to "out", with the
+/// weights read from the `n` × 1 "weights" port.
+#[derive(Clone)]
+pub struct WeightedMux {
+    base: ComponentStruct,
+    dims: af::Dim4,
+    n: usize,
+}
+
+impl WeightedMux {
+    pub fn new(dims: af::Dim4, n: usize) -> Self {
+        let mut base = ComponentStruct::new();
+        for i in 0..n {
+            base.make_in_port(&format!("in{}", i), dims);
+        }
+        base.make_in_port("weights", af::Dim4::new(&[n as u64, 1, 1, 1]));
+        base.make_out_port("out", dims);
+        WeightedMux {
+            base: base,
+            dims: dims,
+            n: n,
+        }
+    }
+}
+
+impl Unit for WeightedMux {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for WeightedMux {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let mut weights = vec![0.0; self.n];
+        self.base.get_input("weights").host(&mut weights);
+        let mut value = af::constant(0.0, self.dims);
+        for i in 0..self.n {
+            let x = self.base.get_input(&format!("in{}", i));
+            value = &value + &(&*x * &af::constant(weights[i], self.dims));
+        }
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn routing_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let n_rows: u64 = 5;
+    let n_cols: u64 = 3;
+
+    let dims = af::Dim4::new(&[n_rows, n_cols, 1, 1]);
+    let unit = af::Dim4::new(&[1, 1, 1, 1]);
+    let ones = af::constant(1.0, dims);
+    let twos = af::constant(2.0, dims);
+
+    let mut c0 = Gate::new(dims);
+    let mut c1 = Mux::new(dims, 2);
+    let mut c2 = Demux::new(dims, 2);
+    let mut c3 = WeightedMux::new(dims, 2);
+
+    c0.get_in_port("in").unwrap().write(Arc::new(ones.clone()));
+    c1.get_in_port("in0").unwrap().write(Arc::new(ones.clone()));
+    c1.get_in_port("in1").unwrap().write(Arc::new(twos.clone()));
+    c1.get_in_port("select").unwrap().write(Arc::new(af::constant(1.0, unit)));
+    c2.get_in_port("in").unwrap().write(Arc::new(twos.clone()));
+    c2.get_in_port("select").unwrap().write(Arc::new(af::constant(1.0, unit)));
+    c3.get_in_port("in0").unwrap().write(Arc::new(ones));
+    c3.get_in_port("in1").unwrap().write(Arc::new(twos));
+    c3.get_in_port("weights").unwrap().write(Arc::new(af::Array::new(&[0.5, 0.25], af::Dim4::new(&[2, 1, 1, 1]))));
+
+    c0.input();
+    c1.input();
+    c2.input();
+    c3.input();
+    c0.fire();
+    c1.fire();
+    c2.fire();
+    c3.fire();
+    c0.output();
+    c1.output();
+    c2.output();
+    c3.output();
+
+    let (r0, _) = af::sum_all(&c0.get_out_port("out").unwrap().read());
+    let (r1, _) = af::sum_all(&c1.get_out_port("out").unwrap().read());
+    let (r2, _) = af::sum_all(&c2.get_out_port("out0").unwrap().read());
+    let (r3, _) = af::sum_all(&c2.get_out_port("out1").unwrap().read());
+    let (r4, _) = af::sum_all(&c3.get_out_port("out").unwrap().read());
+
+    assert_eq!(r0, 0.0);
+    assert_eq!(r1, 30.0);
+    assert_eq!(r2, 0.0);
+    assert_eq!(r3, 30.0);
+    assert_eq!(r4, 15.0);
+
+    c0.get_in_port("control").unwrap().write(Arc::new(af::constant(1.0, unit)));
+    c0.input();
+    c0.fire();
+    c0.output();
+
+    let (r0, _) = af::sum_all(&c0.get_out_port("out").unwrap().read());
+
+    assert_eq!(r0, 15.0);
+
+    c1.get_in_port("select").unwrap().write(Arc::new(af::constant(2.0, unit)));
+    c2.get_in_port("select").unwrap().write(Arc::new(af::constant(-1.0, unit)));
+    c1.input();
+    c2.input();
+    c1.fire();
+    c2.fire();
+    c1.output();
+    c2.output();
+
+    let (r1, _) = af::sum_all(&c1.get_out_port("out").unwrap().read());
+    let (r2, _) = af::sum_all(&c2.get_out_port("out0").unwrap().read());
+    let (r3, _) = af::sum_all(&c2.get_out_port("out1").unwrap().read());
+
+    assert_eq!(r1, 0.0);
+    assert_eq!(r2, 0.0);
+    assert_eq!(r3, 0.0);
+}
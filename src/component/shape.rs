@@ -0,0 +1,406 @@
+use port::Port;
+use unit::*;
+use component::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+fn range(begin: u64, end: u64) -> af::Seq<f64> {
+    af::Seq::new(begin as f64, (end - 1) as f64, 1.0)
+}
+
+fn elements(dims: af::Dim4) -> u64 {
+    dims[0] * dims[1] * dims[2] * dims[3]
+}
+
+fn check_dim(dim: usize) {
+    if dim >= 4 {
+        panic!("Dimension {} out of range (expected: 0..4)", dim);
+    }
+}
+
+/// Writes "in" to "out" with its elements rearranged into `to`, which must
+/// hold the same number of elements.
+#[derive(Clone)]
+pub struct Reshape {
+    base: ComponentStruct,
+    dims: af::Dim4,
+}
+
+impl Reshape {
+    pub fn new(from: af::Dim4, to: af::Dim4) -> Self {
+        if elements(from) != elements(to) {
+            panic!("Mismatched element count (from: {} to: {})", from, to);
+        }
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", from);
+        base.make_out_port("out", to);
+        Reshape {
+            base: base,
+            dims: to,
+        }
+    }
+}
+
+impl Unit for Reshape {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Reshape {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let value = af::moddims(&*x, self.dims);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+/// Swaps the first two dimensions of "in" and writes the result to "out".
+#[derive(Clone)]
+pub struct Transpose {
+    base: ComponentStruct,
+}
+
+impl Transpose {
+    pub fn new(dims: af::Dim4) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", af::Dim4::new(&[dims[1], dims[0], dims[2], dims[3]]));
+        Transpose {
+            base: base,
+        }
+    }
+}
+
+impl Unit for Transpose {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Transpose {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let value = af::transpose(&*x, false);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+/// Writes the sub-array of "in" selected by half-open `(begin, end)` index
+/// ranges to "out". Dimensions without a range are taken whole.
+#[derive(Clone)]
+pub struct Slice {
+    base: ComponentStruct,
+    seqs: Vec<af::Seq<f64>>,
+}
+
+impl Slice {
+    pub fn new(dims: af::Dim4, ranges: &[(u64, u64)]) -> Self {
+        if ranges.len() > 4 {
+            panic!("Too many slice ranges (expected: at most 4 actual: {})", ranges.len());
+        }
+        let mut out = [dims[0], dims[1], dims[2], dims[3]];
+        let mut seqs = Vec::new();
+        for i in 0..4 {
+            let (begin, end) = if i < ranges.len() { ranges[i] } else { (0, dims[i]) };
+            if begin >= end || end > dims[i] {
+                panic!("Invalid slice range {}..{} for dimension {} of size {}", begin, end, i, dims[i]);
+            }
+            out[i] = end - begin;
+            seqs.push(range(begin, end));
+        }
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        base.make_out_port("out", af::Dim4::new(&out));
+        Slice {
+            base: base,
+            seqs: seqs,
+        }
+    }
+}
+
+impl Unit for Slice {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Slice {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let value = af::index(&*x, &self.seqs);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+/// Joins "in0" through "in{n-1}" along `dim` and writes the result to
+/// "out". All other dimensions of the inputs must match.
+#[derive(Clone)]
+pub struct Concat {
+    base: ComponentStruct,
+    dim: usize,
+    n: usize,
+}
+
+impl Concat {
+    pub fn new(dim: usize, inputs: &[af::Dim4]) -> Self {
+        check_dim(dim);
+        if inputs.is_empty() {
+            panic!("Concat requires at least one input");
+        }
+        let mut out = [inputs[0][0], inputs[0][1], inputs[0][2], inputs[0][3]];
+        out[dim] = 0;
+        let mut base = ComponentStruct::new();
+        for (i, dims) in inputs.iter().enumerate() {
+            for j in 0..4 {
+                if j != dim && dims[j] != out[j] {
+                    panic!("Mismatched input dimensions (expected: {} actual: {})", inputs[0], dims);
+                }
+            }
+            out[dim] += dims[dim];
+            base.make_in_port(&format!("in{}", i), *dims);
+        }
+        base.make_out_port("out", af::Dim4::new(&out));
+        Concat {
+            base: base,
+            dim: dim,
+            n: inputs.len(),
+        }
+    }
+}
+
+impl Unit for Concat {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Concat {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let mut value = (*self.base.get_input("in0")).clone();
+        for i in 1..self.n {
+            let x = self.base.get_input(&format!("in{}", i));
+            value = af::join(self.dim as i32, &value, &*x);
+        }
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+/// Cuts "in" along `dim` into consecutive pieces of the given sizes, written
+/// to "out0" through "out{n-1}". The sizes must add up to the input extent.
+#[derive(Clone)]
+pub struct Split {
+    base: ComponentStruct,
+    dim: usize,
+    sizes: Vec<u64>,
+}
+
+impl Split {
+    pub fn new(dims: af::Dim4, dim: usize, sizes: &[u64]) -> Self {
+        check_dim(dim);
+        let total: u64 = sizes.iter().sum();
+        if total != dims[dim] || sizes.contains(&0) {
+            panic!("Split sizes {:?} do not partition dimension {} of size {}", sizes, dim, dims[dim]);
+        }
+        let mut base = ComponentStruct::new();
+        base.make_in_port("in", dims);
+        for (i, &size) in sizes.iter().enumerate() {
+            let mut out = [dims[0], dims[1], dims[2], dims[3]];
+            out[dim] = size;
+            base.make_out_port(&format!("out{}", i), af::Dim4::new(&out));
+        }
+        Split {
+            base: base,
+            dim: dim,
+            sizes: sizes.to_vec(),
+        }
+    }
+}
+
+impl Unit for Split {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Split {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let x = self.base.get_input("in");
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        let mut begin = 0;
+        for (i, &size) in self.sizes.iter().enumerate() {
+            let mut seqs = vec![af::Seq::default(); 4];
+            seqs[self.dim] = range(begin, begin + size);
+            outputs.insert(format!("out{}", i), Arc::new(af::index(&*x, &seqs)));
+            begin += size;
+        }
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn shape_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let n_rows: u64 = 5;
+    let n_cols: u64 = 3;
+
+    let dims = af::Dim4::new(&[n_rows, n_cols, 1, 1]);
+    let flat = af::Dim4::new(&[n_rows * n_cols, 1, 1, 1]);
+    let values: Vec<f64> = (0..15).map(|x| x as f64).collect();
+
+    let mut c0 = Reshape::new(dims, flat);
+    let mut c1 = Transpose::new(dims);
+    let mut c2 = Slice::new(dims, &[(1, 3), (0, 2)]);
+    let mut c3 = Split::new(dims, 1, &[1, 2]);
+    let mut c4 = Concat::new(1, &[af::Dim4::new(&[n_rows, 1, 1, 1]), af::Dim4::new(&[n_rows, 2, 1, 1])]);
+
+    connect(&mut c4, "in0", &mut c3, "out0");
+    connect(&mut c4, "in1", &mut c3, "out1");
+
+    for c in [&mut c0 as &mut Component, &mut c1, &mut c2, &mut c3].iter_mut() {
+        c.get_in_port("in").unwrap().write(Arc::new(af::Array::new(&values, dims)));
+    }
+
+    for _ in 0..2 {
+        c0.input();
+        c1.input();
+        c2.input();
+        c3.input();
+        c4.input();
+        c0.fire();
+        c1.fire();
+        c2.fire();
+        c3.fire();
+        c4.fire();
+        c0.output();
+        c1.output();
+        c2.output();
+        c3.output();
+        c4.output();
+    }
+
+    let a0 = c0.get_out_port("out").unwrap().read();
+    let a1 = c1.get_out_port("out").unwrap().read();
+    let a2 = c2.get_out_port("out").unwrap().read();
+    let a3 = c3.get_out_port("out1").unwrap().read();
+    let a4 = c4.get_out_port("out").unwrap().read();
+
+    assert_eq!(a0.dims(), flat);
+    assert_eq!(a1.dims(), af::Dim4::new(&[n_cols, n_rows, 1, 1]));
+    assert_eq!(a2.dims(), af::Dim4::new(&[2, 2, 1, 1]));
+    assert_eq!(a3.dims(), af::Dim4::new(&[n_rows, 2, 1, 1]));
+    assert_eq!(a4.dims(), dims);
+
+    let (r2, _) = af::sum_all(&a2);
+    let (r3, _) = af::sum_all(&a3);
+    let (r4, _) = af::sum_all(&a4);
+
+    assert_eq!(r2, 1.0 + 2.0 + 6.0 + 7.0);
+    assert_eq!(r3, 105.0 - 10.0);
+    assert_eq!(r4, 105.0);
+}
@@ -0,0 +1,307 @@
+use port::Port;
+use unit::*;
+use component::*;
+use random::Stream;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::Arc;
+use af;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Algorithm {
+    QLearning,
+    SARSA,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Exploration {
+    EpsilonGreedy(f64),
+    Softmax(f64),
+}
+
+/// Tabular temporal-difference agent. Each step reads the current state
+/// index from "state", the reward for the previous action from "reward" and
+/// a nonzero "done" flag at episode ends, updates the Q-table for the
+/// previous state-action pair, and writes the next action index to
+/// "action". All ports are 1 × 1. A state that is not a valid index is
+/// ignored: nothing is learned, the pending transition is dropped, and the
+/// action is -1.
+#[derive(Clone)]
+pub struct TabularAgent {
+    base: ComponentStruct,
+    algorithm: Algorithm,
+    exploration: Exploration,
+    alpha: f64,
+    gamma: f64,
+    table: af::Array,
+    previous: Option<(usize, usize)>,
+    stream: Stream,
+}
+
+impl TabularAgent {
+    pub fn new(algorithm: Algorithm, n_states: u64, n_actions: u64, alpha: f64, gamma: f64, exploration: Exploration, stream: Stream) -> Self {
+        match exploration {
+            Exploration::EpsilonGreedy(epsilon) if !(epsilon >= 0.0 && epsilon <= 1.0) => {
+                panic!("Exploration rate {} out of range (expected: 0..1)", epsilon);
+            },
+            Exploration::Softmax(temperature) if !(temperature > 0.0) => {
+                panic!("Softmax temperature must be positive (actual: {})", temperature);
+            },
+            _ => {},
+        }
+        let unit = af::Dim4::new(&[1, 1, 1, 1]);
+        let mut base = ComponentStruct::new();
+        base.make_in_port("state", unit);
+        base.make_in_port("reward", unit);
+        base.make_in_port("done", unit);
+        base.make_out_port("action", unit);
+        TabularAgent {
+            base: base,
+            algorithm: algorithm,
+            exploration: exploration,
+            alpha: alpha,
+            gamma: gamma,
+            table: af::constant(0.0, af::Dim4::new(&[n_states, n_actions, 1, 1])),
+            previous: None,
+            stream: stream,
+        }
+    }
+
+    pub fn q_learning(n_states: u64, n_actions: u64, alpha: f64, gamma: f64, exploration: Exploration, stream: Stream) -> Self {
+        TabularAgent::new(Algorithm::QLearning, n_states, n_actions, alpha, gamma, exploration, stream)
+    }
+
+    pub fn sarsa(n_states: u64, n_actions: u64, alpha: f64, gamma: f64, exploration: Exploration, stream: Stream) -> Self {
+        TabularAgent::new(Algorithm::SARSA, n_states, n_actions, alpha, gamma, exploration, stream)
+    }
+
+    pub fn get_table(&self) -> &af::Array {
+        &self.table
+    }
+
+    pub fn set_table(&mut self, table: af::Array) {
+        if self.table.dims() != table.dims() {
+            panic!("Mismatched Q-table dimensions (expected: {} actual: {})", self.table.dims(), table.dims());
+        }
+        self.table = table;
+    }
+
+    /// Writes the Q-table as one line of comma-separated action values per
+    /// state.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let (n_states, n_actions) = self.shape();
+        let values = self.host_table();
+        let mut file = try!(File::create(path));
+        for s in 0..n_states {
+            let row: Vec<String> = (0..n_actions).map(|a| values[s + a * n_states].to_string()).collect();
+            try!(writeln!(file, "{}", row.join(",")));
+        }
+        Ok(())
+    }
+
+    pub fn load(&mut self, path: &str) -> io::Result<()> {
+        let (n_states, n_actions) = self.shape();
+        let mut values = vec![0.0; n_states * n_actions];
+        let file = try!(File::open(path));
+        let mut rows = 0;
+        for (s, line) in BufReader::new(file).lines().enumerate() {
+            let line = try!(line);
+            let row: Vec<&str> = line.split(',').collect();
+            if s >= n_states || row.len() != n_actions {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Q-table shape mismatch"));
+            }
+            for (a, cell) in row.iter().enumerate() {
+                values[s + a * n_states] = try!(cell.trim().parse::<f64>()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)));
+            }
+            rows += 1;
+        }
+        if rows != n_states {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Q-table shape mismatch"));
+        }
+        self.table = af::Array::new(&values, self.table.dims());
+        Ok(())
+    }
+
+    fn shape(&self) -> (usize, usize) {
+        let dims = self.table.dims();
+        (dims[0] as usize, dims[1] as usize)
+    }
+
+    fn host_table(&self) -> Vec<f64> {
+        let (n_states, n_actions) = self.shape();
+        let mut values = vec![0.0; n_states * n_actions];
+        self.table.host(&mut values);
+        values
+    }
+
+    fn choose(&mut self, values: &[f64], state: usize) -> usize {
+        let (n_states, n_actions) = self.shape();
+        let row: Vec<f64> = (0..n_actions).map(|a| values[state + a * n_states]).collect();
+        match self.exploration {
+            Exploration::EpsilonGreedy(epsilon) => {
+                if self.stream.next_f64() < epsilon {
+                    (self.stream.next_u64() % n_actions as u64) as usize
+                } else {
+                    greedy(&row)
+                }
+            },
+            Exploration::Softmax(temperature) => {
+                let best = row[greedy(&row)];
+                let weights: Vec<f64> = row.iter().map(|q| ((q - best) / temperature).exp()).collect();
+                self.stream.categorical(&weights)
+            },
+        }
+    }
+
+    fn step(&mut self, state: usize, reward: f64, done: bool) -> usize {
+        let (n_states, n_actions) = self.shape();
+        let mut values = self.host_table();
+
+        // SARSA bootstraps from the action it is about to take, so that
+        // action has to be drawn before the update rather than after it.
+        let sarsa_action = match self.algorithm {
+            Algorithm::SARSA => Some(self.choose(&values, state)),
+            Algorithm::QLearning => None,
+        };
+
+        if let Some((s, a)) = self.previous {
+            let next = match sarsa_action {
+                Some(action) => values[state + action * n_states],
+                None => {
+                    let row: Vec<f64> = (0..n_actions).map(|b| values[state + b * n_states]).collect();
+                    row[greedy(&row)]
+                },
+            };
+            let bootstrap = if done { 0.0 } else { self.gamma * next };
+            let index = s + a * n_states;
+            values[index] += self.alpha * (reward + bootstrap - values[index]);
+            self.table = af::Array::new(&values, self.table.dims());
+        }
+
+        let action = match sarsa_action {
+            Some(action) => action,
+            None => self.choose(&values, state),
+        };
+        self.previous = if done { None } else { Some((state, action)) };
+        action
+    }
+}
+
+fn greedy(row: &[f64]) -> usize {
+    let mut best = 0;
+    for a in 1..row.len() {
+        if row[a] > row[best] {
+            best = a;
+        }
+    }
+    best
+}
+
+impl Unit for TabularAgent {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for TabularAgent {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let (n_states, _) = self.shape();
+        let reward = scalar(&*self.base.get_input("reward"));
+        let done = scalar(&*self.base.get_input("done")) != 0.0;
+        let action = match select(&*self.base.get_input("state"), n_states) {
+            Some(state) => self.step(state, reward, done) as f64,
+            None        => {
+                self.previous = None;
+                -1.0
+            },
+        };
+        let value = af::constant(action, af::Dim4::new(&[1, 1, 1, 1]));
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("action".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+#[test]
+fn tabular_works() {
+    use random::Random;
+    use std::{env, fs, process};
+
+    af::set_backend(af::Backend::CPU);
+
+    let unit = af::Dim4::new(&[1, 1, 1, 1]);
+    let random = Random::new(0);
+
+    let mut c0 = TabularAgent::q_learning(2, 2, 0.5, 0.9, Exploration::EpsilonGreedy(0.0), random.stream("c0"));
+    let mut c1 = TabularAgent::sarsa(2, 2, 0.5, 0.9, Exploration::Softmax(1.0), random.stream("c1"));
+
+    for c in [&mut c0, &mut c1].iter_mut() {
+        c.get_in_port("state").unwrap().write(Arc::new(af::constant(0.0, unit)));
+        c.input();
+        c.fire();
+        c.output();
+
+        c.get_in_port("state").unwrap().write(Arc::new(af::constant(1.0, unit)));
+        c.get_in_port("reward").unwrap().write(Arc::new(af::constant(1.0, unit)));
+        c.get_in_port("done").unwrap().write(Arc::new(af::constant(1.0, unit)));
+        c.input();
+        c.fire();
+        c.output();
+    }
+
+    let (r0, _) = af::sum_all(c0.get_table());
+    let (r1, _) = af::sum_all(c1.get_table());
+
+    assert_eq!(r0, 0.5);
+    assert_eq!(r1, 0.5);
+
+    let path = env::temp_dir().join(format!("brica2_tabular_works_{}.csv", process::id()));
+    let path = path.to_str().unwrap();
+    c0.save(path).unwrap();
+    c1.load(path).unwrap();
+    fs::remove_file(path).unwrap();
+
+    let (r1, _) = af::sum_all(&af::abs(&(c0.get_table() - c1.get_table())));
+
+    assert_eq!(r1, 0.0);
+
+    let mut actions = Vec::new();
+
+    c0.get_in_port("done").unwrap().write(Arc::new(af::constant(0.0, unit)));
+    c0.get_in_port("reward").unwrap().write(Arc::new(af::constant(5.0, unit)));
+    for &state in [0.0, 7.0, 1.0].iter() {
+        c0.get_in_port("state").unwrap().write(Arc::new(af::constant(state, unit)));
+        c0.input();
+        c0.fire();
+        c0.output();
+
+        let (r2, _) = af::sum_all(&c0.get_out_port("action").unwrap().read());
+        actions.push(r2);
+    }
+
+    let (r0, _) = af::sum_all(c0.get_table());
+
+    assert_eq!(actions[1], -1.0);
+    assert_eq!(r0, 0.5);
+}
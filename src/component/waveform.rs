@@ -0,0 +1,197 @@
+use port::Port;
+use unit::*;
+use component::*;
+use clock::Clock;
+use registry::{Registry, expect_args};
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::sync::Arc;
+use af;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Wave {
+    Sine,
+    Square,
+    Sawtooth,
+    Chirp { target: f64, duration: f64 },
+}
+
+/// Emits `amplitude * wave(t)` on "out" each step, where `t` is the current
+/// time of `clock`. Generators sharing a clock stay in phase.
+#[derive(Clone)]
+pub struct Waveform {
+    base: ComponentStruct,
+    dims: af::Dim4,
+    wave: Wave,
+    amplitude: f64,
+    frequency: f64,
+    phase: f64,
+    clock: Clock,
+}
+
+impl Waveform {
+    pub fn new(dims: af::Dim4, wave: Wave, amplitude: f64, frequency: f64, phase: f64, clock: Clock) -> Self {
+        let mut base = ComponentStruct::new();
+        base.make_out_port("out", dims);
+        Waveform {
+            base: base,
+            dims: dims,
+            wave: wave,
+            amplitude: amplitude,
+            frequency: frequency,
+            phase: phase,
+            clock: clock,
+        }
+    }
+
+    pub fn sine(dims: af::Dim4, amplitude: f64, frequency: f64, phase: f64, clock: Clock) -> Self {
+        Waveform::new(dims, Wave::Sine, amplitude, frequency, phase, clock)
+    }
+
+    pub fn square(dims: af::Dim4, amplitude: f64, frequency: f64, phase: f64, clock: Clock) -> Self {
+        Waveform::new(dims, Wave::Square, amplitude, frequency, phase, clock)
+    }
+
+    pub fn sawtooth(dims: af::Dim4, amplitude: f64, frequency: f64, phase: f64, clock: Clock) -> Self {
+        Waveform::new(dims, Wave::Sawtooth, amplitude, frequency, phase, clock)
+    }
+
+    pub fn chirp(dims: af::Dim4, amplitude: f64, frequency: f64, target: f64, duration: f64, phase: f64, clock: Clock) -> Self {
+        let wave = Wave::Chirp { target: target, duration: duration };
+        Waveform::new(dims, wave, amplitude, frequency, phase, clock)
+    }
+
+    pub fn value_at(&self, t: f64) -> f64 {
+        let cycles = self.frequency * t + self.phase / (2.0 * PI);
+        let value = match self.wave {
+            Wave::Sine => (2.0 * PI * cycles).sin(),
+            Wave::Square => if cycles - cycles.floor() < 0.5 { 1.0 } else { -1.0 },
+            Wave::Sawtooth => 2.0 * (cycles - cycles.floor()) - 1.0,
+            Wave::Chirp { target, duration } => {
+                let rate = (target - self.frequency) / duration;
+                (2.0 * PI * (cycles + 0.5 * rate * t * t)).sin()
+            },
+        };
+        self.amplitude * value
+    }
+}
+
+impl Unit for Waveform {
+    delegate! {
+        for base;
+        fn make_in_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_in_port(&mut self, key: &str);
+        fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn make_out_port(&mut self, key: &str, dims: af::Dim4);
+        fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
+        fn remove_out_port(&mut self, key: &str);
+        fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
+        fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
+    }
+}
+
+impl Component for Waveform {
+    delegate! {
+        for base;
+        fn input(&mut self);
+        fn output(&mut self);
+        fn get_input(&mut self, key: &str) -> Arc<af::Array>;
+        fn get_output(&mut self, key: &str) -> Arc<af::Array>;
+    }
+
+    fn fire(&mut self) {
+        let value = af::constant(self.value_at(self.clock.get_time()), self.dims);
+        let mut outputs = BTreeMap::<String, Arc<af::Array>>::new();
+        outputs.insert("out".to_string(), Arc::new(value));
+        self.base.outputs = outputs;
+    }
+}
+
+/// Registers "sine", "square" and "sawtooth", each taking the output shape
+/// and the amplitude, frequency and phase, and "chirp", which takes the
+/// amplitude, frequency, target frequency, duration and phase. All of them
+/// read time from `clock`.
+pub fn register(registry: &mut Registry, clock: &Clock) {
+    let periodic = [
+        ("sine", Wave::Sine),
+        ("square", Wave::Square),
+        ("sawtooth", Wave::Sawtooth),
+    ];
+    for &(name, wave) in periodic.iter() {
+        let clock = clock.clone();
+        registry.register(name, move |dims, params| {
+            expect_args(name, dims, params, 1, 3);
+            Arc::new(Waveform::new(dims[0], wave, params[0], params[1], params[2], clock.clone()))
+        });
+    }
+
+    let clock = clock.clone();
+    registry.register("chirp", move |dims, params| {
+        expect_args("chirp", dims, params, 1, 5);
+        Arc::new(Waveform::chirp(dims[0], params[0], params[1], params[2], params[3], params[4], clock.clone()))
+    });
+}
+
+#[test]
+fn waveform_works() {
+    af::set_backend(af::Backend::CPU);
+
+    let n_rows: u64 = 5;
+    let n_cols: u64 = 3;
+
+    let dims = af::Dim4::new(&[n_rows, n_cols, 1, 1]);
+    let clock = Clock::new(0.25);
+
+    let mut c0 = Waveform::sine(dims, 2.0, 1.0, 0.0, clock.clone());
+    let c1 = Waveform::square(dims, 1.0, 1.0, 0.0, clock.clone());
+    let c2 = Waveform::sawtooth(dims, 1.0, 1.0, 0.0, clock.clone());
+    let c3 = Waveform::chirp(dims, 1.0, 1.0, 3.0, 1.0, 0.0, clock.clone());
+
+    assert_eq!(c1.value_at(0.25), 1.0);
+    assert_eq!(c1.value_at(0.75), -1.0);
+    assert_eq!(c2.value_at(0.0), -1.0);
+    assert_eq!(c2.value_at(0.5), 0.0);
+    assert!(c3.value_at(0.0).abs() < 1e-12);
+
+    let mut registry = Registry::new();
+    register(&mut registry, &clock);
+
+    assert_eq!(registry.get_names(), vec!["chirp", "sawtooth", "sine", "square"]);
+
+    let mut c4 = registry.make("sine", &[dims], &[2.0, 1.0, 0.0]);
+    let c4 = Arc::get_mut(&mut c4).unwrap();
+
+    let mut sums = Vec::new();
+
+    for _ in 0..2 {
+        c0.input();
+        c4.input();
+        c0.fire();
+        c4.fire();
+        c0.output();
+        c4.output();
+        clock.tick();
+
+        let (r0, _) = af::sum_all(&c0.get_out_port("out").unwrap().read());
+        let (r4, _) = af::sum_all(&c4.get_out_port("out").unwrap().read());
+        sums.push((r0, r4));
+    }
+
+    assert!(sums[0].0.abs() < 1e-9);
+    assert!((sums[1].0 - 30.0).abs() < 1e-9);
+    assert_eq!(sums[0].0, sums[0].1);
+    assert_eq!(sums[1].0, sums[1].1);
+    assert_eq!(clock.get_time(), 0.5);
+
+    clock.reset();
+    c0.input();
+    c0.fire();
+    c0.output();
+
+    let (r0, _) = af::sum_all(&c0.get_out_port("out").unwrap().read());
+
+    assert!(r0.abs() < 1e-9);
+}
@@ -6,6 +6,9 @@ pub mod port;
 pub mod unit;
 pub mod component;
 pub mod module;
+pub mod random;
+pub mod clock;
+pub mod registry;
 
 #[cfg(test)]
 mod tests {
@@ -1,30 +1,106 @@
 use port::Port;
 use unit::*;
 use component::*;
-use std::collections::HashMap;
+use random::{Random, Stream};
+use clock::Clock;
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 use af;
 
+/// Components and submodules are stored keyed by name in a `BTreeMap`, so
+/// walking over a module always visits its children in name order.
+///
+/// A module also carries the network's `Random` service and `Clock`, and
+/// knows its own path from the root. Submodules are made with
+/// `make_submodule`, which shares both services and extends the path, and
+/// `add_submodule` rejects any module made for another key or network.
+/// Stochastic components are added with `add_component_with_stream`, which
+/// hands the constructor the stream of the component's own path.
 #[derive(Clone)]
 pub struct Module {
     unit: UnitStruct,
-    components: HashMap<String, Arc<Mutex<Arc<Component>>>>,
-    submodules: HashMap<String, Arc<Mutex<Arc<Module>>>>,
+    components: BTreeMap<String, Arc<Mutex<Arc<Component>>>>,
+    submodules: BTreeMap<String, Arc<Mutex<Arc<Module>>>>,
+    random: Random,
+    clock: Clock,
+    path: String,
 }
 
 impl Module {
     pub fn new() -> Self {
         Module {
             unit: UnitStruct::new(),
-            components: HashMap::new(),
-            submodules: HashMap::new(),
+            components: BTreeMap::new(),
+            submodules: BTreeMap::new(),
+            random: Random::new(0),
+            clock: Clock::new(1.0),
+            path: String::new(),
         }
     }
 
+    pub fn with_random(mut self, random: Random) -> Self {
+        self.check_empty();
+        self.random = random;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.check_empty();
+        self.clock = clock;
+        self
+    }
+
+    /// Creates an empty module to be added under `key`, sharing this
+    /// module's random service and clock and nested under its path.
+    pub fn make_submodule(&self, key: &str) -> Module {
+        let mut submodule = Module::new()
+            .with_random(self.random.clone())
+            .with_clock(self.clock.clone());
+        submodule.path = self.child_path(key);
+        submodule
+    }
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn get_random(&self) -> &Random {
+        &self.random
+    }
+
+    pub fn get_clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    fn check_empty(&self) {
+        if !self.components.is_empty() || !self.submodules.is_empty() {
+            panic!("Module `{}` already has children", self.path);
+        }
+    }
+
+    fn child_path(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{}", self.path, key)
+        }
+    }
+
+    fn stream(&self, key: &str) -> Stream {
+        self.random.stream(&self.child_path(key))
+    }
+
     pub fn add_component(&mut self, key: &str, component: Arc<Component>) {
         self.components.insert(key.to_string(), Arc::new(Mutex::new(component)));
     }
 
+    pub fn add_component_with_stream<F>(&mut self, key: &str, make: F)
+        where F: FnOnce(Stream) -> Arc<Component>
+    {
+        let component = make(self.stream(key));
+        self.add_component(key, component);
+    }
+
     pub fn get_component(&mut self, key: &str) -> Arc<Mutex<Arc<Component>>> {
         match self.components.get_mut(&key.to_string()) {
             Some(x) => x.clone(),
@@ -32,7 +108,18 @@ impl Module {
         }
     }
 
+    pub fn get_components(&mut self) -> &mut BTreeMap<String, Arc<Mutex<Arc<Component>>>> {
+        &mut self.components
+    }
+
     pub fn add_submodule(&mut self, key: &str, submodule: Arc<Module>) {
+        let path = self.child_path(key);
+        if submodule.path != path {
+            panic!("Mismatched submodule path (expected: {} actual: {})", path, submodule.path);
+        }
+        if submodule.random.get_seed() != self.random.get_seed() || !submodule.clock.is_shared_with(&self.clock) {
+            panic!("Submodule `{}` belongs to another network", path);
+        }
         self.submodules.insert(key.to_string(), Arc::new(Mutex::new(submodule)));
     }
 
@@ -42,6 +129,10 @@ impl Module {
             None    => panic!("Component `{}` does not exist", key),
         }
     }
+
+    pub fn get_submodules(&mut self) -> &mut BTreeMap<String, Arc<Mutex<Arc<Module>>>> {
+        &mut self.submodules
+    }
 }
 
 impl Unit for Module {
@@ -49,18 +140,72 @@ impl Unit for Module {
         for unit;
         fn make_in_port(&mut self, key: &str, dims: af::Dim4);
         fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
-        fn get_in_ports(&mut self) -> &mut HashMap<String, Port>;
+        fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
         fn remove_in_port(&mut self, key: &str);
         fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
         fn make_out_port(&mut self, key: &str, dims: af::Dim4);
         fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
-        fn get_out_ports(&mut self) -> &mut HashMap<String, Port>;
+        fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
         fn remove_out_port(&mut self, key: &str);
         fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
         fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
     }
 }
 
+#[test]
+fn module_children_are_ordered() {
+    use component::null::Null;
+
+    af::set_backend(af::Backend::CPU);
+
+    let mut m0 = Module::new();
+
+    m0.add_component("c2", Arc::new(Null::new()));
+    m0.add_component("c0", Arc::new(Null::new()));
+    m0.add_component("c1", Arc::new(Null::new()));
+    let submodule = m0.make_submodule("m1");
+    m0.add_submodule("m1", Arc::new(submodule));
+    let submodule = m0.make_submodule("m0");
+    m0.add_submodule("m0", Arc::new(submodule));
+
+    let component_keys: Vec<String> = m0.get_components().keys().cloned().collect();
+    let submodule_keys: Vec<String> = m0.get_submodules().keys().cloned().collect();
+
+    assert_eq!(component_keys, vec!["c0", "c1", "c2"]);
+    assert_eq!(submodule_keys, vec!["m0", "m1"]);
+}
+
+#[test]
+fn module_streams_follow_paths() {
+    use component::null::Null;
+
+    af::set_backend(af::Backend::CPU);
+
+    let random = Random::new(42);
+    let clock = Clock::new(0.5);
+    let mut m0 = Module::new().with_random(random.clone()).with_clock(clock.clone());
+    let mut m1 = m0.make_submodule("m1");
+    let m2 = m1.make_submodule("m2");
+
+    assert_eq!(m2.get_path(), "m1/m2");
+    assert!(m2.get_clock().is_shared_with(&clock));
+    assert_eq!(m0.stream("c0").get_seed(), random.stream("c0").get_seed());
+    assert_eq!(m1.stream("c0").get_seed(), random.stream("m1/c0").get_seed());
+    assert_eq!(m2.stream("c0").get_seed(), random.stream("m1/m2/c0").get_seed());
+    assert!(m1.stream("c0").get_seed() != m2.stream("c0").get_seed());
+
+    let mut seeds = Vec::new();
+
+    m1.add_component_with_stream("c0", |stream| {
+        seeds.push(stream.get_seed());
+        Arc::new(Null::new())
+    });
+    m1.add_submodule("m2", Arc::new(m2));
+    m0.add_submodule("m1", Arc::new(m1));
+
+    assert_eq!(seeds, vec![random.stream("m1/c0").get_seed()]);
+}
+
 #[test]
 fn module_works() {
     use component::constant::Constant;
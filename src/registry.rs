@@ -0,0 +1,80 @@
+use component::Component;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use af;
+
+pub type Constructor = Box<Fn(&[af::Dim4], &[f64]) -> Arc<Component>>;
+
+/// Maps component names to constructors, so a network can be described by
+/// name. Each constructor takes the port shapes and numeric parameters that
+/// the registering module documents for that name. Component modules add
+/// their names through a `register` function.
+pub struct Registry {
+    constructors: BTreeMap<String, Constructor>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry {
+            constructors: BTreeMap::new(),
+        }
+    }
+
+    pub fn register<F>(&mut self, name: &str, constructor: F)
+        where F: Fn(&[af::Dim4], &[f64]) -> Arc<Component> + 'static
+    {
+        if self.constructors.contains_key(name) {
+            panic!("Component `{}` is already registered", name);
+        }
+        self.constructors.insert(name.to_string(), Box::new(constructor));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.constructors.contains_key(name)
+    }
+
+    pub fn get_names(&self) -> Vec<String> {
+        self.constructors.keys().cloned().collect()
+    }
+
+    pub fn make(&self, name: &str, dims: &[af::Dim4], params: &[f64]) -> Arc<Component> {
+        match self.constructors.get(name) {
+            Some(constructor) => constructor(dims, params),
+            None              => panic!("Component `{}` is not registered", name),
+        }
+    }
+}
+
+/// Checks the argument counts of a registered constructor.
+pub fn expect_args(name: &str, dims: &[af::Dim4], params: &[f64], n_dims: usize, n_params: usize) {
+    if dims.len() != n_dims || params.len() != n_params {
+        panic!("Component `{}` takes {} shapes and {} parameters (actual: {} and {})",
+               name, n_dims, n_params, dims.len(), params.len());
+    }
+}
+
+#[test]
+fn registry_works() {
+    use component::null::Null;
+
+    af::set_backend(af::Backend::CPU);
+
+    let dims = af::Dim4::new(&[5, 3, 1, 1]);
+    let mut registry = Registry::new();
+
+    registry.register("sink", |dims, params| {
+        expect_args("sink", dims, params, 1, 0);
+        let mut component = Null::new();
+        component.make_in_port("in", dims[0]);
+        Arc::new(component)
+    });
+
+    assert!(registry.contains("sink"));
+    assert!(!registry.contains("source"));
+    assert_eq!(registry.get_names(), vec!["sink"]);
+
+    let mut c0 = registry.make("sink", &[dims], &[]);
+    let c0 = Arc::get_mut(&mut c0).unwrap();
+
+    assert_eq!(c0.get_in_port("in").unwrap().read().dims(), dims);
+}
@@ -1,16 +1,18 @@
 use port::Port;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use af;
 
+/// Ports are stored keyed by name in a `BTreeMap`, so iterating over
+/// `get_in_ports` and `get_out_ports` always visits ports in name order.
 pub trait Unit {
     fn make_in_port(&mut self, key: &str, dims: af::Dim4);
     fn get_in_port(&mut self, key: &str) -> Result<&mut Port, String>;
-    fn get_in_ports(&mut self) -> &mut HashMap<String, Port>;
+    fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port>;
     fn remove_in_port(&mut self, key: &str);
     fn alias_in_port(&mut self, from: &str, other: &mut Unit, to: &str);
     fn make_out_port(&mut self, key: &str, dims: af::Dim4);
     fn get_out_port(&mut self, key: &str) -> Result<&mut Port, String>;
-    fn get_out_ports(&mut self) -> &mut HashMap<String, Port>;
+    fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port>;
     fn remove_out_port(&mut self, key: &str);
     fn alias_out_port(&mut self, from: &str, other: &mut Unit, to: &str);
     fn connect(&mut self, from: &str, other: &mut Unit, to: &str);
@@ -18,15 +20,15 @@ pub trait Unit {
 
 #[derive(Clone)]
 pub struct UnitStruct {
-    in_ports: HashMap<String, Port>,
-    out_ports: HashMap<String, Port>,
+    in_ports: BTreeMap<String, Port>,
+    out_ports: BTreeMap<String, Port>,
 }
 
 impl UnitStruct {
     pub fn new() -> Self {
         UnitStruct {
-            in_ports: HashMap::new(),
-            out_ports: HashMap::new(),
+            in_ports: BTreeMap::new(),
+            out_ports: BTreeMap::new(),
         }
     }
 }
@@ -43,7 +45,7 @@ impl Unit for UnitStruct {
         }
     }
 
-    fn get_in_ports(&mut self) -> &mut HashMap<String, Port> {
+    fn get_in_ports(&mut self) -> &mut BTreeMap<String, Port> {
         &mut self.in_ports
     }
 
@@ -68,7 +70,7 @@ impl Unit for UnitStruct {
         }
     }
 
-    fn get_out_ports(&mut self) -> &mut HashMap<String, Port> {
+    fn get_out_ports(&mut self) -> &mut BTreeMap<String, Port> {
         &mut self.out_ports
     }
 
@@ -92,3 +94,25 @@ impl Unit for UnitStruct {
 pub fn connect(from_unit: &mut Unit, from_port: &str, to_unit: &mut Unit, to_port: &str) {
     from_unit.connect(from_port, to_unit, to_port);
 }
+
+#[test]
+fn unit_ports_are_ordered() {
+    af::set_backend(af::Backend::CPU);
+
+    let dims = af::Dim4::new(&[1, 1, 1, 1]);
+
+    let mut u0 = UnitStruct::new();
+
+    u0.make_in_port("c", dims);
+    u0.make_in_port("a", dims);
+    u0.make_in_port("b", dims);
+    u0.make_out_port("z", dims);
+    u0.make_out_port("x", dims);
+    u0.make_out_port("y", dims);
+
+    let in_keys: Vec<String> = u0.get_in_ports().keys().cloned().collect();
+    let out_keys: Vec<String> = u0.get_out_ports().keys().cloned().collect();
+
+    assert_eq!(in_keys, vec!["a", "b", "c"]);
+    assert_eq!(out_keys, vec!["x", "y", "z"]);
+}